@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS disabled_commands (
+	command_name TEXT PRIMARY KEY,
+	disabled_by BIGINT NOT NULL,
+	disabled_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS mention_notifications (
+	user_id BIGINT PRIMARY KEY,
+	mode TEXT NOT NULL,
+	enabled_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS auto_delete_invocations (
+	channel_id BIGINT NOT NULL,
+	command_name TEXT NOT NULL,
+	configured_by BIGINT NOT NULL,
+	configured_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY (channel_id, command_name)
+);
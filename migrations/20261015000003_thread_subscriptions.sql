@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS thread_subscriptions (
+	thread_id BIGINT NOT NULL,
+	user_id BIGINT NOT NULL,
+	expires_at TIMESTAMPTZ NOT NULL,
+	last_notified_at TIMESTAMPTZ,
+	PRIMARY KEY (thread_id, user_id)
+);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS coc_acceptances (
+	user_id BIGINT PRIMARY KEY,
+	accepted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
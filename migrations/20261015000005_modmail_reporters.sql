@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS modmail_reporters (
+	thread_id BIGINT PRIMARY KEY,
+	user_id BIGINT NOT NULL,
+	anonymous BOOLEAN NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS message_counts (
+	user_id BIGINT NOT NULL,
+	day DATE NOT NULL,
+	count INTEGER NOT NULL,
+	PRIMARY KEY (user_id, day)
+);
+
+CREATE TABLE IF NOT EXISTS leaderboard_opt_outs (
+	user_id BIGINT PRIMARY KEY,
+	opted_out_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS server_icons (
+	guild_id BIGINT PRIMARY KEY,
+	icon_name TEXT NOT NULL,
+	changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -1,4 +1,7 @@
+use std::collections::HashSet;
+
 use anyhow::{anyhow, Error};
+use poise::serenity_prelude as serenity;
 
 use crate::types::Context;
 
@@ -21,3 +24,113 @@ pub async fn check_is_moderator(ctx: Context<'_>) -> Result<bool, Error> {
 
 	Ok(user_has_moderator_role)
 }
+
+/// Parses the optional, comma-separated `OWNER_IDS` secret. These users are bot owners in
+/// addition to the owners of the Discord application, which poise adds on startup.
+pub fn parse_owner_ids(owner_ids: Option<&str>) -> Result<HashSet<serenity::UserId>, Error> {
+	owner_ids
+		.unwrap_or_default()
+		.split(',')
+		.map(str::trim)
+		.filter(|id| !id.is_empty())
+		.map(|id| {
+			id.parse::<u64>()
+				.map(serenity::UserId::from)
+				.map_err(|e| anyhow!("Invalid user ID `{}` in 'OWNER_IDS': {}", id, e))
+		})
+		.collect()
+}
+
+pub fn is_owner(ctx: Context<'_>, user_id: serenity::UserId) -> bool {
+	ctx.framework().options().owners.contains(&user_id)
+}
+
+pub async fn check_is_owner(ctx: Context<'_>) -> Result<bool, Error> {
+	let user_is_owner = is_owner(ctx, ctx.author().id);
+
+	if !user_is_owner {
+		ctx.send(
+			poise::CreateReply::default()
+				.content("This command is only available to the bot owners.")
+				.ephemeral(true),
+		)
+		.await?;
+	}
+
+	Ok(user_is_owner)
+}
+
+/// Rejects commands that were disabled at runtime via `?disable`
+pub async fn check_command_enabled(ctx: Context<'_>) -> Result<bool, Error> {
+	// Disabling a command also disables all of its subcommands
+	let is_disabled = {
+		let disabled_commands = ctx.data().disabled_commands.read().unwrap();
+		std::iter::once(ctx.command())
+			.chain(ctx.parent_commands().iter().copied())
+			.any(|command| disabled_commands.contains(&command.qualified_name))
+	};
+
+	if is_disabled {
+		ctx.send(
+			poise::CreateReply::default()
+				.content("This command is temporarily disabled.")
+				.ephemeral(true),
+		)
+		.await?;
+	}
+
+	Ok(!is_disabled)
+}
+
+/// Refuses commands that need the database while it's unavailable, see
+/// [`crate::database::DatabaseHealth`]
+pub async fn check_database_available(ctx: Context<'_>) -> Result<bool, Error> {
+	let data = ctx.data();
+	let is_available = data.database_health.is_available(&data.database).await;
+
+	if !is_available {
+		ctx.send(
+			poise::CreateReply::default()
+				.content(crate::database::UNAVAILABLE_MESSAGE)
+				.ephemeral(true),
+		)
+		.await?;
+	}
+
+	Ok(is_available)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_comma_separated_owner_ids() {
+		let owners = parse_owner_ids(Some("123,456")).unwrap();
+		assert_eq!(owners.len(), 2);
+		assert!(owners.contains(&serenity::UserId::new(123)));
+		assert!(owners.contains(&serenity::UserId::new(456)));
+		assert!(!owners.contains(&serenity::UserId::new(789)));
+	}
+
+	#[test]
+	fn skips_whitespace_and_empty_owner_ids() {
+		let owners = parse_owner_ids(Some(" 123 , ,456,, ")).unwrap();
+		assert_eq!(
+			owners,
+			HashSet::from([serenity::UserId::new(123), serenity::UserId::new(456)])
+		);
+		assert!(parse_owner_ids(Some("")).unwrap().is_empty());
+	}
+
+	#[test]
+	fn rejects_invalid_owner_ids() {
+		let error = parse_owner_ids(Some("123,not-an-id")).unwrap_err();
+		assert!(error.to_string().contains("`not-an-id`"));
+	}
+
+	#[test]
+	fn no_owner_ids_without_the_secret() {
+		assert!(parse_owner_ids(None).unwrap().is_empty());
+	}
+}
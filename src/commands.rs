@@ -1,8 +1,18 @@
 pub use godbolt::*;
 pub use playground::*;
 
+pub mod auto_delete;
+pub mod ban_sweep;
 pub mod crates;
 pub mod godbolt;
+pub mod leaderboard;
+pub mod mentions;
 pub mod modmail;
+pub mod onboarding;
 pub mod playground;
+pub mod rustup;
+pub mod server_icons;
+pub mod stats;
+pub mod threads;
+pub mod twir;
 pub mod utilities;
@@ -0,0 +1,261 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use serenity::Mentionable;
+use tracing::warn;
+
+use crate::commands::utilities::resolve_command_name;
+use crate::types::{Context, InvocationState};
+
+/// Removes the invocation from poise's edit tracker. Commands that track edits also track the
+/// deletion of their invocation and would delete the bot's response along with it, which is not
+/// what auto-deleting is for.
+fn forget_tracked_invocation(
+	edit_tracker: &std::sync::RwLock<poise::EditTracker>,
+	invocation_id: serenity::MessageId,
+) {
+	// Returns the response that poise would have deleted
+	let _ = edit_tracker
+		.write()
+		.unwrap()
+		.process_message_delete(invocation_id);
+}
+
+/// Deletes the message that triggered this prefix command invocation if moderators configured
+/// that for this command in this channel. Does nothing for slash commands, where there's no
+/// message to delete, and in channels where the bot can't manage messages.
+///
+/// Commands that keep running after responding (e.g. to await button presses) can call this right
+/// after responding; it's called again after every successful command but only deletes once.
+pub async fn delete_invocation_if_configured(ctx: Context<'_>) {
+	let poise::Context::Prefix(prefix_context) = ctx else {
+		return;
+	};
+
+	let is_configured = ctx
+		.data()
+		.auto_delete_invocations
+		.read()
+		.unwrap()
+		.contains(&(ctx.channel_id(), ctx.command().qualified_name.clone()));
+	if !is_configured || InvocationState::get(ctx).await.invocation_deleted {
+		return;
+	}
+
+	let Some(channel) = ctx.guild_channel().await else {
+		return;
+	};
+	let can_manage_messages = channel
+		.permissions_for_user(ctx, ctx.framework().bot_id)
+		.is_ok_and(|permissions| permissions.manage_messages());
+	if !can_manage_messages {
+		return;
+	}
+
+	InvocationState::update(ctx, |state| state.invocation_deleted = true).await;
+	if let Some(edit_tracker) = &ctx.framework().options().prefix_options.edit_tracker {
+		forget_tracked_invocation(edit_tracker, prefix_context.msg.id);
+	}
+	// The message may already be gone if the user deleted it themselves
+	if let Err(e) = prefix_context.msg.delete(ctx).await {
+		warn!("Couldn't auto-delete invocation message: {}", e);
+	}
+}
+
+/// Auto-delete command invocations in a channel
+///
+/// Configures commands whose triggering messages get deleted once the bot responded, e.g. \
+/// `?play` in showcase channels. Only applies to prefix commands.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "auto-delete",
+	subcommands("auto_delete_on", "auto_delete_off", "auto_delete_list"),
+	subcommand_required,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn auto_delete(_: Context<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Auto-delete invocations of a command in a channel
+///
+/// /auto-delete on <command> [channel]
+///
+/// Deletes the messages invoking the given command once the bot responded. Defaults to the current
+/// channel.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "on",
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn auto_delete_on(
+	ctx: Context<'_>,
+	#[description = "Command whose invocations to delete"]
+	#[autocomplete = "poise::builtins::autocomplete_command"]
+	command: String,
+	#[description = "Channel (defaults to this one)"] channel: Option<serenity::ChannelId>,
+) -> Result<(), Error> {
+	let command_name = resolve_command_name(ctx, &command)?;
+	let channel_id = channel.unwrap_or(ctx.channel_id());
+
+	let newly_added = crate::database::auto_delete_invocations::add(
+		&ctx.data().database,
+		channel_id,
+		&command_name,
+		ctx.author().id,
+	)
+	.await?;
+	ctx.data().refresh_auto_delete_invocations().await?;
+
+	if newly_added {
+		ctx.say(format!(
+			"Invocations of `{}` in {} will be deleted once the bot responded. Note that this \
+			requires the bot to have the Manage Messages permission there.",
+			command_name,
+			channel_id.mention()
+		))
+		.await?;
+	} else {
+		ctx.say(format!(
+			"Invocations of `{}` in {} are already auto-deleted.",
+			command_name,
+			channel_id.mention()
+		))
+		.await?;
+	}
+
+	Ok(())
+}
+
+/// Stop auto-deleting invocations of a command in a channel
+///
+/// /auto-delete off <command> [channel]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "off",
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn auto_delete_off(
+	ctx: Context<'_>,
+	#[description = "Command whose invocations to keep"]
+	#[autocomplete = "poise::builtins::autocomplete_command"]
+	command: String,
+	#[description = "Channel (defaults to this one)"] channel: Option<serenity::ChannelId>,
+) -> Result<(), Error> {
+	let command_name = resolve_command_name(ctx, &command)?;
+	let channel_id = channel.unwrap_or(ctx.channel_id());
+
+	let removed = crate::database::auto_delete_invocations::remove(
+		&ctx.data().database,
+		channel_id,
+		&command_name,
+	)
+	.await?;
+	ctx.data().refresh_auto_delete_invocations().await?;
+
+	if removed {
+		ctx.say(format!(
+			"Invocations of `{}` in {} won't be deleted anymore.",
+			command_name,
+			channel_id.mention()
+		))
+		.await?;
+	} else {
+		ctx.say(format!(
+			"Invocations of `{}` in {} weren't auto-deleted.",
+			command_name,
+			channel_id.mention()
+		))
+		.await?;
+	}
+
+	Ok(())
+}
+
+/// List where command invocations are auto-deleted
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "list",
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn auto_delete_list(ctx: Context<'_>) -> Result<(), Error> {
+	let settings = crate::database::auto_delete_invocations::get_all(&ctx.data().database).await?;
+	if settings.is_empty() {
+		ctx.say("No command invocations are auto-deleted.").await?;
+		return Ok(());
+	}
+
+	let list = settings
+		.iter()
+		.map(|setting| {
+			format!(
+				"- `{}` in <#{}>, set by <@{}>",
+				setting.command_name, setting.channel_id, setting.configured_by
+			)
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+	ctx.say(format!(
+		"Command invocations are auto-deleted for:\n{}",
+		list
+	))
+	.await?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn message(id: u64) -> serenity::Message {
+		let mut message = serenity::Message::default();
+		message.id = serenity::MessageId::new(id);
+		message
+	}
+
+	#[test]
+	fn deleting_a_forgotten_invocation_keeps_the_response() {
+		let edit_tracker = poise::EditTracker::for_timespan(std::time::Duration::from_secs(60));
+		let invocation = message(1);
+		edit_tracker
+			.write()
+			.unwrap()
+			.set_bot_response(&invocation, message(2), true);
+
+		forget_tracked_invocation(&edit_tracker, invocation.id);
+
+		// What poise does once the invocation is deleted
+		let deleted_response = edit_tracker
+			.write()
+			.unwrap()
+			.process_message_delete(invocation.id);
+		assert!(deleted_response.is_none());
+	}
+
+	#[test]
+	fn deleting_a_tracked_invocation_deletes_the_response() {
+		let edit_tracker = poise::EditTracker::for_timespan(std::time::Duration::from_secs(60));
+		let invocation = message(1);
+		edit_tracker
+			.write()
+			.unwrap()
+			.set_bot_response(&invocation, message(2), true);
+
+		let deleted_response = edit_tracker
+			.write()
+			.unwrap()
+			.process_message_delete(invocation.id);
+		assert_eq!(deleted_response.map(|response| response.id.get()), Some(2));
+	}
+}
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Error};
+use futures::StreamExt;
+use poise::serenity_prelude as serenity;
+use tracing::info;
+
+use crate::types::Context;
+
+/// Most accounts a single sweep may ban, so that loose criteria can't ban half the server
+const MAX_SWEEP_TARGETS: usize = 200;
+
+/// How many of the matching accounts are listed in the preview
+const MAX_PREVIEWED_TARGETS: usize = 25;
+
+/// How long the moderator has to confirm the sweep after seeing the preview
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The progress message is updated after this many accounts
+const PROGRESS_UPDATE_INTERVAL: usize = 10;
+
+/// Discord's limit for audit log reasons
+const MAX_AUDIT_LOG_REASON_LENGTH: usize = 512;
+
+/// One line describing a member for the preview and summary
+fn describe_member(member: &serenity::Member) -> String {
+	format!(
+		"{} ({}), created <t:{}:R>, joined <t:{}:R>",
+		member.user.name,
+		member.user.id,
+		member.user.created_at().unix_timestamp(),
+		member.joined_at.unwrap_or_default().unix_timestamp(),
+	)
+}
+
+/// Bans the member unless they got a role since the preview, e.g. because they were verified in
+/// the meantime, or rank too high to be moderated
+async fn ban_if_still_matching(
+	ctx: Context<'_>,
+	hierarchy: &crate::helpers::RoleHierarchy,
+	member: &serenity::Member,
+	audit_log_reason: &str,
+) -> Result<(), Error> {
+	let member = member.guild_id.member(ctx, member.user.id).await?;
+	if !member.roles.is_empty() {
+		bail!("got a role since the preview");
+	}
+	hierarchy.can_act_on(&member)?;
+	member.ban_with_reason(ctx, 0, audit_log_reason).await?;
+	Ok(())
+}
+
+/// Ban accounts matching raid heuristics
+///
+/// /ban-sweep <created_within_hours> <joined_within_hours> [reason]
+///
+/// Finds members without roles whose account was created and who joined within the given number \
+/// of hours. You get a preview of who would be banned first, and nothing happens until you \
+/// confirm. Members that you or the bot can't moderate are skipped.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	required_bot_permissions = "BAN_MEMBERS",
+	rename = "ban-sweep",
+	category = "Utilities"
+)]
+pub async fn ban_sweep(
+	ctx: Context<'_>,
+	#[description = "Only accounts created within this many hours"] created_within_hours: u32,
+	#[description = "Only members who joined within this many hours"] joined_within_hours: u32,
+	#[description = "Reason for the audit log"]
+	#[rest]
+	reason: Option<String>,
+) -> Result<(), Error> {
+	let guild_id = ctx
+		.guild_id()
+		.ok_or(anyhow!("This command can only be used in a server"))?;
+	let now = chrono::Utc::now();
+	let created_after = now - chrono::Duration::hours(created_within_hours.into());
+	let joined_after = now - chrono::Duration::hours(joined_within_hours.into());
+
+	ctx.defer().await?;
+
+	let mut targets = Vec::new();
+	let mut members = std::pin::pin!(guild_id.members_iter(ctx));
+	while let Some(member) = members.next().await {
+		let member = member?;
+		if !member.user.bot
+			&& member.roles.is_empty()
+			&& *member.user.created_at() > created_after
+			&& member
+				.joined_at
+				.is_some_and(|joined_at| *joined_at > joined_after)
+		{
+			targets.push(member);
+		}
+	}
+
+	if targets.is_empty() {
+		ctx.say("No members match these criteria.").await?;
+		return Ok(());
+	}
+	if targets.len() > MAX_SWEEP_TARGETS {
+		bail!(
+			"{} members match, but a sweep can ban at most {}. Please narrow down the criteria.",
+			targets.len(),
+			MAX_SWEEP_TARGETS
+		);
+	}
+
+	let mut preview = targets
+		.iter()
+		.take(MAX_PREVIEWED_TARGETS)
+		.map(|member| format!("- {}\n", describe_member(member)))
+		.collect::<String>();
+	if targets.len() > MAX_PREVIEWED_TARGETS {
+		preview += &format!("…and {} more\n", targets.len() - MAX_PREVIEWED_TARGETS);
+	}
+
+	let confirm_id = format!("{}confirm", ctx.id());
+	let cancel_id = format!("{}cancel", ctx.id());
+	let reply = ctx
+		.send(
+			poise::CreateReply::default()
+				.embed(
+					serenity::CreateEmbed::new()
+						.color(crate::types::EMBED_COLOR)
+						.title(format!("Ban {} members?", targets.len()))
+						.description(preview),
+				)
+				.components(vec![serenity::CreateActionRow::Buttons(vec![
+					serenity::CreateButton::new(&confirm_id)
+						.label("Ban them")
+						.style(serenity::ButtonStyle::Danger),
+					serenity::CreateButton::new(&cancel_id)
+						.label("Cancel")
+						.style(serenity::ButtonStyle::Secondary),
+				])]),
+		)
+		.await?;
+	let message = reply.message().await?.into_owned();
+
+	let press = message
+		.await_component_interaction(ctx)
+		.author_id(ctx.author().id)
+		.timeout(CONFIRMATION_TIMEOUT)
+		.await;
+	let confirmed = match &press {
+		Some(press) => {
+			press.defer(ctx).await?;
+			press.data.custom_id == confirm_id
+		}
+		None => false,
+	};
+	if !confirmed {
+		reply
+			.edit(
+				ctx,
+				poise::CreateReply::default()
+					.content("Ban sweep cancelled, nobody was banned.")
+					.components(Vec::new()),
+			)
+			.await?;
+		return Ok(());
+	}
+
+	let audit_log_reason = format!(
+		"Ban sweep by {}: {}",
+		ctx.author().name,
+		reason.as_deref().unwrap_or("raid")
+	)
+	.chars()
+	.take(MAX_AUDIT_LOG_REASON_LENGTH)
+	.collect::<String>();
+	info!(
+		"{} started a ban sweep of {} members: {}",
+		ctx.author().name,
+		targets.len(),
+		audit_log_reason
+	);
+
+	let hierarchy = crate::helpers::RoleHierarchy::fetch(ctx).await?;
+	let mut banned = 0;
+	let mut skipped = String::new();
+	for (i, member) in targets.iter().enumerate() {
+		if i % PROGRESS_UPDATE_INTERVAL == 0 {
+			reply
+				.edit(
+					ctx,
+					poise::CreateReply::default()
+						.content(format!("Banning… {}/{}", i, targets.len()))
+						.components(Vec::new()),
+				)
+				.await?;
+		}
+
+		match ban_if_still_matching(ctx, &hierarchy, member, &audit_log_reason).await {
+			Ok(()) => {
+				info!("Ban sweep banned {}", describe_member(member));
+				banned += 1;
+			}
+			Err(e) => skipped += &format!("- {}: {}\n", member.user.name, e),
+		}
+	}
+
+	let mut summary = format!(
+		"Ban sweep done, banned {} of {} members.",
+		banned,
+		targets.len()
+	);
+	if !skipped.is_empty() {
+		summary += &format!("\nSkipped:\n{}", skipped);
+	}
+	reply
+		.edit(
+			ctx,
+			poise::CreateReply::default()
+				.content(crate::helpers::trim_text(&summary, "", async { "\n…".to_owned() }).await),
+		)
+		.await?;
+
+	Ok(())
+}
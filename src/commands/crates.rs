@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
 use anyhow::{anyhow, bail};
 use reqwest::header;
@@ -9,12 +12,29 @@ use crate::types::Context;
 
 const USER_AGENT: &str = "kangalioo/rustbot";
 
+/// Most crates listed by `?crates-trending`
+const MAX_TRENDING_CRATES: usize = 50;
+
+/// Crates requested per page of the crates.io listing
+const TRENDING_CRATES_PER_REQUEST: usize = 25;
+
+const TRENDING_CRATES_PER_PAGE: usize = 10;
+
+/// crates.io asks crawlers to keep to one request per second
+const CRATES_IO_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The listings don't change much within an hour, and this keeps the bot well within the crates.io
+/// rate limits
+const TRENDING_CACHE_DURATION: Duration = Duration::from_secs(60 * 60);
+
+const TRENDING_DESCRIPTION_LENGTH: usize = 80;
+
 #[derive(Debug, Deserialize)]
 struct Crates {
 	crates: Vec<Crate>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct Crate {
 	name: String,
 	// newest_version: String, // https://github.com/kangalioo/rustbot/issues/23
@@ -23,6 +43,7 @@ struct Crate {
 	// sometimes null empirically
 	updated_at: String,
 	downloads: u64,
+	recent_downloads: Option<u64>,
 	description: Option<String>,
 	documentation: Option<String>,
 	exact_match: bool,
@@ -98,6 +119,198 @@ async fn autocomplete_crate(ctx: Context<'_>, partial: &str) -> impl Iterator<It
 		.map(|crate_| crate_.name)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, poise::ChoiceParameter)]
+pub enum TrendingSort {
+	#[name = "recent"]
+	RecentDownloads,
+	#[name = "downloads"]
+	AllTimeDownloads,
+	#[name = "updated"]
+	RecentlyUpdated,
+}
+
+impl TrendingSort {
+	fn api_name(self) -> &'static str {
+		match self {
+			Self::RecentDownloads => "recent-downloads",
+			Self::AllTimeDownloads => "downloads",
+			Self::RecentlyUpdated => "recent-updates",
+		}
+	}
+
+	fn title(self) -> &'static str {
+		match self {
+			Self::RecentDownloads => "Most downloaded crates in the last 90 days",
+			Self::AllTimeDownloads => "Most downloaded crates of all time",
+			Self::RecentlyUpdated => "Recently updated crates",
+		}
+	}
+}
+
+/// Crate listings fetched by `?crates-trending`, per sort order
+#[derive(Debug, Default)]
+pub struct TrendingCratesCache {
+	listings: HashMap<TrendingSort, (Instant, Vec<Crate>)>,
+}
+
+/// Fetches the first [`MAX_TRENDING_CRATES`] crates of the crates.io listing in the given order,
+/// going through as many pages of the listing as needed
+async fn fetch_trending_crates(http: &reqwest::Client, sort: TrendingSort) -> Result<Vec<Crate>> {
+	info!("fetching {} crates listing", sort.api_name());
+
+	let mut crates = Vec::new();
+	for page in 1.. {
+		if page > 1 {
+			tokio::time::sleep(CRATES_IO_REQUEST_INTERVAL).await;
+		}
+
+		let response = http
+			.get("https://crates.io/api/v1/crates")
+			.header(header::USER_AGENT, USER_AGENT)
+			.query(&[
+				("sort", sort.api_name()),
+				("per_page", &TRENDING_CRATES_PER_REQUEST.to_string()),
+				("page", &page.to_string()),
+			])
+			.send()
+			.await?;
+		if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+			bail!("crates.io is rate limiting the bot, please try again in a few minutes");
+		}
+		let page_crates = response
+			.error_for_status()?
+			.json::<Crates>()
+			.await
+			.map_err(|e| anyhow!("Cannot parse crates.io JSON response (`{}`)", e))?
+			.crates;
+
+		let is_last_page = page_crates.len() < TRENDING_CRATES_PER_REQUEST;
+		crates.extend(page_crates);
+		if is_last_page || crates.len() >= MAX_TRENDING_CRATES {
+			break;
+		}
+	}
+
+	crates.truncate(MAX_TRENDING_CRATES);
+	Ok(crates)
+}
+
+/// Returns the crates listing in the given order, fetched at most once per
+/// [`TRENDING_CACHE_DURATION`]
+async fn trending_crates(ctx: Context<'_>, sort: TrendingSort) -> Result<Vec<Crate>> {
+	let cached = ctx
+		.data()
+		.trending_crates
+		.lock()
+		.unwrap()
+		.listings
+		.get(&sort)
+		.filter(|(fetched_at, _)| fetched_at.elapsed() < TRENDING_CACHE_DURATION)
+		.map(|(_, crates)| crates.clone());
+	if let Some(crates) = cached {
+		return Ok(crates);
+	}
+
+	let crates = fetch_trending_crates(&ctx.data().http, sort).await?;
+	ctx.data()
+		.trending_crates
+		.lock()
+		.unwrap()
+		.listings
+		.insert(sort, (Instant::now(), crates.clone()));
+	Ok(crates)
+}
+
+fn format_trending_crate(rank: usize, crate_: &Crate, sort: TrendingSort) -> String {
+	let statistic = match sort {
+		TrendingSort::RecentDownloads => format!(
+			"{} recent downloads",
+			format_number(crate_.recent_downloads.unwrap_or_default())
+		),
+		TrendingSort::AllTimeDownloads => format!("{} downloads", format_number(crate_.downloads)),
+		TrendingSort::RecentlyUpdated => {
+			match chrono::DateTime::parse_from_rfc3339(&crate_.updated_at) {
+				Ok(updated_at) => format!("updated <t:{}:R>", updated_at.timestamp()),
+				Err(_) => format!("updated {}", crate_.updated_at),
+			}
+		}
+	};
+
+	let mut line = format!(
+		"{}. [{}](https://crates.io/crates/{}) – {}",
+		rank, crate_.name, crate_.name, statistic
+	);
+	if let Some(description) = &crate_.description {
+		let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+		if description.chars().count() > TRENDING_DESCRIPTION_LENGTH {
+			line += &format!(
+				"\n> {}…",
+				description
+					.chars()
+					.take(TRENDING_DESCRIPTION_LENGTH - 1)
+					.collect::<String>()
+			);
+		} else {
+			line += &format!("\n> {}", description);
+		}
+	}
+	line
+}
+
+/// List the most popular or recently updated crates
+///
+/// /crates-trending [sort]
+///
+/// Lists the top crates on crates.io, sorted by downloads in the last 90 days (`recent`, the \
+/// default), all-time downloads (`downloads`) or by when they were last updated (`updated`). \
+/// Listings are refreshed at most once an hour.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	rename = "crates-trending",
+	aliases("trending-crates"),
+	broadcast_typing,
+	category = "Crates"
+)]
+pub async fn crates_trending(
+	ctx: Context<'_>,
+	#[description = "How to sort the crates (default: recent downloads)"] sort: Option<
+		TrendingSort,
+	>,
+) -> Result<()> {
+	let sort = sort.unwrap_or(TrendingSort::RecentDownloads);
+	ctx.defer().await?;
+
+	let crates = trending_crates(ctx, sort).await?;
+	if crates.is_empty() {
+		bail!("crates.io didn't return any crates");
+	}
+
+	let pages = crates
+		.chunks(TRENDING_CRATES_PER_PAGE)
+		.enumerate()
+		.map(|(page_index, chunk)| {
+			let lines = chunk
+				.iter()
+				.enumerate()
+				.map(|(i, crate_)| {
+					format_trending_crate(
+						page_index * TRENDING_CRATES_PER_PAGE + i + 1,
+						crate_,
+						sort,
+					)
+				})
+				.collect::<Vec<_>>()
+				.join("\n");
+			format!("**{}**\n\n{}", sort.title(), lines)
+		})
+		.collect::<Vec<_>>();
+
+	poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+
+	Ok(())
+}
+
 /// Lookup crates on crates.io
 ///
 /// Search for a crate on crates.io
@@ -158,6 +371,126 @@ pub async fn crate_(
 	Ok(())
 }
 
+#[derive(Debug, Deserialize)]
+struct Dependencies {
+	dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dependency {
+	crate_id: String,
+	req: String,
+	kind: String,
+	optional: bool,
+}
+
+/// Queries the dependencies of a specific crate version
+async fn get_dependencies(
+	http: &reqwest::Client,
+	crate_name: &str,
+	version: &str,
+) -> Result<Vec<Dependency>> {
+	let dependencies = http
+		.get(format!(
+			"https://crates.io/api/v1/crates/{}/{}/dependencies",
+			crate_name, version
+		))
+		.header(header::USER_AGENT, USER_AGENT)
+		.send()
+		.await?
+		.json::<Dependencies>()
+		.await
+		.map_err(|e| anyhow!("Cannot parse crates.io JSON response (`{}`)", e))?
+		.dependencies;
+	Ok(dependencies)
+}
+
+/// Lists dependencies as lines, cut off to fit into an embed field
+fn format_dependency_list(dependencies: &[&Dependency]) -> String {
+	const MAX_FIELD_LENGTH: usize = 1024;
+
+	let mut list = String::new();
+	for (i, dependency) in dependencies.iter().enumerate() {
+		let line = format!(
+			"`{}` {}{}\n",
+			dependency.crate_id,
+			dependency.req,
+			if dependency.optional {
+				" (optional)"
+			} else {
+				""
+			}
+		);
+		let remaining = format!("…and {} more", dependencies.len() - i);
+		if list.len() + line.len() + remaining.len() > MAX_FIELD_LENGTH {
+			list += &remaining;
+			break;
+		}
+		list += &line;
+	}
+	list
+}
+
+/// Show the dependencies of a crate
+///
+/// Lists the direct dependencies of the latest version of a crate on crates.io, grouped by kind
+/// ```
+/// ?crate-deps crate_name
+/// ```
+#[poise::command(
+	prefix_command,
+	slash_command,
+	rename = "crate-deps",
+	broadcast_typing,
+	category = "Crates"
+)]
+pub async fn crate_deps(
+	ctx: Context<'_>,
+	#[description = "Name of the crate"]
+	#[autocomplete = "autocomplete_crate"]
+	crate_name: String,
+) -> Result<()> {
+	let crate_ = get_crate(&ctx.data().http, &crate_name).await?;
+	let version = crate_
+		.max_stable_version
+		.or(crate_.max_version)
+		.ok_or_else(|| anyhow!("Crate `{}` has no published versions", crate_.name))?;
+
+	let dependencies = get_dependencies(&ctx.data().http, &crate_.name, &version).await?;
+
+	let mut embed = serenity::CreateEmbed::new()
+		.title(format!("Dependencies of {} {}", crate_.name, version))
+		.url(format!(
+			"https://crates.io/crates/{}/{}/dependencies",
+			crate_.name, version
+		))
+		.color(crate::types::EMBED_COLOR);
+	if dependencies.is_empty() {
+		embed = embed.description("This crate has no dependencies");
+	}
+	for (kind, title) in [
+		("normal", "Dependencies"),
+		("build", "Build dependencies"),
+		("dev", "Dev dependencies"),
+	] {
+		let dependencies_of_kind = dependencies
+			.iter()
+			.filter(|dependency| dependency.kind == kind)
+			.collect::<Vec<_>>();
+		if !dependencies_of_kind.is_empty() {
+			embed = embed.field(
+				format!("{} ({})", title, dependencies_of_kind.len()),
+				format_dependency_list(&dependencies_of_kind),
+				false,
+			);
+		}
+	}
+
+	ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+	Ok(())
+}
+
 /// Returns whether the given type name is the one of a primitive.
 #[rustfmt::skip]
 fn is_in_std(name: &str) -> bool {
@@ -188,12 +521,84 @@ fn rustc_crate_link(crate_name: &str) -> Option<&'static str> {
 	}
 }
 
+/// Whether the string looks like a full semver version, e.g. `1.0.200` or `0.4.0-alpha.1`
+fn is_semver(version: &str) -> bool {
+	let core_version = version.split(['-', '+']).next().unwrap_or_default();
+	let parts = core_version.split('.').collect::<Vec<_>>();
+	parts.len() == 3
+		&& parts
+			.iter()
+			.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Resolves a documentation query like `serde::Serialize` or `serde@1.0.200::Serialize` to a URL.
+/// Without a version, the docs of the latest version are linked.
+async fn path_to_doc_url(http: &reqwest::Client, query: &str) -> Result<String> {
+	let mut query_iter = query.splitn(2, "::");
+	let first_path_element = query_iter.next().unwrap();
+	let item_path = query_iter.next();
+
+	let (first_path_element, version) = match first_path_element.split_once('@') {
+		Some((crate_name, version)) => {
+			if !is_semver(version) {
+				bail!(
+					"`{}` is not a valid version, expected something like `1.0.200`",
+					version
+				);
+			}
+			(crate_name, Some(version))
+		}
+		None => (first_path_element, None),
+	};
+
+	// Versioned docs of official crates live under the version instead of "stable"
+	let versioned_rustc_link = |link: &str| match version {
+		Some(version) => link.replacen("/stable/", &format!("/{}/", version), 1),
+		None => link.to_owned(),
+	};
+
+	let mut doc_url = if let Some(rustc_crate) = rustc_crate_link(first_path_element) {
+		versioned_rustc_link(rustc_crate)
+	} else if first_path_element.is_empty() || is_in_std(first_path_element) {
+		versioned_rustc_link("https://doc.rust-lang.org/stable/std/")
+	} else {
+		let crate_ = get_crate(http, first_path_element).await?;
+		match version {
+			Some(version) => format!(
+				"https://docs.rs/{}/{}/{}/",
+				crate_.name,
+				version,
+				crate_.name.replace('-', "_")
+			),
+			None => get_documentation(&crate_),
+		}
+	};
+
+	if is_in_std(first_path_element) {
+		doc_url += "?search=";
+		doc_url += first_path_element;
+		if let Some(item_path) = item_path {
+			doc_url += "::";
+			doc_url += item_path;
+		}
+	} else if let Some(item_path) = item_path {
+		doc_url += "?search=";
+		doc_url += item_path;
+	}
+
+	Ok(doc_url)
+}
+
 /// Lookup documentation
 ///
 /// Retrieve documentation for a given crate
 /// ```
 /// ?docs crate_name::module::item
 /// ```
+/// To link the docs of a specific version, add it after the crate name:
+/// ```
+/// ?docs crate_name@1.0.200::module::item
+/// ```
 #[poise::command(
 	prefix_command,
 	aliases("docs"),
@@ -206,26 +611,74 @@ pub async fn doc(
 	ctx: Context<'_>,
 	#[description = "Path of the crate and item to lookup"] query: String,
 ) -> Result<()> {
-	let mut query_iter = query.splitn(2, "::");
-	let first_path_element = query_iter.next().unwrap();
+	let doc_url = path_to_doc_url(&ctx.data().http, &query).await?;
 
-	let mut doc_url = if let Some(rustc_crate) = rustc_crate_link(first_path_element) {
-		rustc_crate.to_owned()
-	} else if first_path_element.is_empty() || is_in_std(first_path_element) {
-		"https://doc.rust-lang.org/stable/std/".to_owned()
-	} else {
-		get_documentation(&get_crate(&ctx.data().http, first_path_element).await?)
-	};
+	ctx.say(doc_url).await?;
 
-	if is_in_std(first_path_element) {
-		doc_url += "?search=";
-		doc_url += &query;
-	} else if let Some(item_path) = query_iter.next() {
-		doc_url += "?search=";
-		doc_url += item_path;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn semver_versions() {
+		assert!(is_semver("1.0.200"));
+		assert!(is_semver("0.4.0"));
+		assert!(is_semver("0.4.0-alpha.1"));
+		assert!(is_semver("1.0.0+build.5"));
+		assert!(is_semver("1.0.0-rc.1+build.5"));
+
+		assert!(!is_semver("1.0"));
+		assert!(!is_semver("1"));
+		assert!(!is_semver("1.0.0.0"));
+		assert!(!is_semver("1..0"));
+		assert!(!is_semver("latest"));
+		assert!(!is_semver("1.x.0"));
+		assert!(!is_semver(""));
 	}
 
-	ctx.say(doc_url).await?;
+	/// Resolves queries that are answered without asking crates.io
+	fn offline_doc_url(query: &str) -> Result<String> {
+		futures::executor::block_on(path_to_doc_url(&reqwest::Client::new(), query))
+	}
 
-	Ok(())
+	#[test]
+	fn std_doc_urls() {
+		assert_eq!(
+			offline_doc_url("std::vec::Vec").unwrap(),
+			"https://doc.rust-lang.org/stable/std/?search=vec::Vec"
+		);
+		assert_eq!(
+			offline_doc_url("std@1.70.0::vec::Vec").unwrap(),
+			"https://doc.rust-lang.org/1.70.0/std/?search=vec::Vec"
+		);
+		assert_eq!(
+			offline_doc_url("Vec").unwrap(),
+			"https://doc.rust-lang.org/stable/std/?search=Vec"
+		);
+		assert_eq!(
+			offline_doc_url("u32::checked_add").unwrap(),
+			"https://doc.rust-lang.org/stable/std/?search=u32::checked_add"
+		);
+	}
+
+	#[test]
+	fn rustc_doc_urls() {
+		assert_eq!(
+			offline_doc_url("rustc::rustc_middle::ty").unwrap(),
+			"https://doc.rust-lang.org/nightly/nightly-rustc/?search=rustc_middle::ty"
+		);
+		assert_eq!(
+			offline_doc_url("nightly").unwrap(),
+			"https://doc.rust-lang.org/nightly/std/"
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_doc_versions() {
+		let error = offline_doc_url("std@1.70::vec::Vec").unwrap_err();
+		assert!(error.to_string().contains("`1.70`"));
+	}
 }
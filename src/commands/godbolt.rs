@@ -1,4 +1,8 @@
+use std::time::Duration;
+
 use anyhow::{anyhow, Error};
+use futures::StreamExt;
+use poise::serenity_prelude as serenity;
 use tracing::warn;
 
 use crate::types::Context;
@@ -8,12 +12,30 @@ pub use targets::*;
 
 const LLVM_MCA_TOOL_ID: &str = "llvm-mcatrunk";
 
+/// How many compilations ?godbolt-compare runs at once, to be kind to godbolt
+const MAX_CONCURRENT_COMPILATIONS: usize = 2;
+
 struct Compilation {
 	output: String,
 	stderr: String,
 	success: bool,
 }
 
+impl Compilation {
+	/// The text to show for this compilation: the output with any warnings in front of it, or the
+	/// compiler diagnostics, trimmed like the playground's, if compilation failed
+	fn text(&self) -> std::borrow::Cow<'_, str> {
+		if !self.success {
+			let diagnostics =
+				crate::commands::playground::format_play_eval_stderr(&self.stderr, true);
+			if !diagnostics.trim().is_empty() {
+				return diagnostics.into();
+			}
+		}
+		crate::helpers::merge_output_and_errors(&self.output, &self.stderr)
+	}
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct GodboltOutputSegment {
 	text: String,
@@ -61,9 +83,10 @@ struct GodboltRequest<'a> {
 /// full optimizations (-O3)
 /// Returns a multiline string with the pretty printed assembly
 async fn compile_rust_source(
-	http: &reqwest::Client,
+	ctx: Context<'_>,
 	request: &GodboltRequest<'_>,
 ) -> Result<Compilation, Error> {
+	let http = &ctx.data().http;
 	let tools = if request.run_llvm_mca {
 		serde_json::json! {
 			[{"id": LLVM_MCA_TOOL_ID}]
@@ -90,9 +113,11 @@ async fn compile_rust_source(
         } })
 		.build()?;
 
-	let response: GodboltResponse = http.execute(http_request).await?.json().await?;
+	let response: GodboltResponse = crate::helpers::with_compile_slot(ctx, async {
+		Ok(http.execute(http_request).await?.json().await?)
+	})
+	.await?;
 
-	// TODO: use the extract_relevant_lines utility to strip stderr nicely
 	Ok(Compilation {
 		output: if request.run_llvm_mca {
 			let text = response
@@ -168,6 +193,12 @@ enum GodboltMode {
 	Mca,
 }
 
+/// Most messages posted when showing the full output in a thread, to not flood it
+const MAX_THREAD_MESSAGES: usize = 10;
+
+/// How long the button to show the full output in a thread stays around
+const THREAD_BUTTON_TIMEOUT: Duration = Duration::from_secs(300);
+
 async fn respond_codeblock(
 	ctx: Context<'_>,
 	codeblock_lang: &str,
@@ -175,23 +206,149 @@ async fn respond_codeblock(
 	note: &str,
 	godbolt_request: &GodboltRequest<'_>,
 ) -> Result<(), Error> {
-	ctx.say(
-		crate::helpers::trim_text(
-			&format!("```{}\n{}", codeblock_lang, text),
-			&format!("\n```{}", note),
-			async {
-				format!(
-					"Output too large. Godbolt link: <{}>",
-					save_to_shortlink(&ctx.data().http, godbolt_request).await,
-				)
-			},
+	let text_body = format!("```{}\n{}", codeblock_lang, text);
+	let text_end = format!("\n```{}", note);
+	if !crate::helpers::needs_trimming(&text_body, &text_end) {
+		ctx.say(text_body + &text_end).await?;
+		return Ok(());
+	}
+
+	let reply_text = crate::helpers::trim_text(&text_body, &text_end, async {
+		format!(
+			"Output too large. Godbolt link: <{}>",
+			save_to_shortlink(&ctx.data().http, godbolt_request).await,
 		)
-		.await,
-	)
-	.await?;
+	})
+	.await;
+
+	let thread_button_id = format!("{}thread", ctx.id());
+	let response = ctx
+		.send(
+			poise::CreateReply::default()
+				.content(reply_text)
+				.components(vec![serenity::CreateActionRow::Buttons(vec![
+					serenity::CreateButton::new(&thread_button_id)
+						.label("View all in thread")
+						.style(serenity::ButtonStyle::Secondary),
+				])]),
+		)
+		.await?;
+	// This keeps running for a while to wait for the button, so don't wait for that
+	crate::commands::auto_delete::delete_invocation_if_configured(ctx).await;
+	let message = response.message().await?.into_owned();
+
+	let press = message
+		.await_component_interaction(ctx)
+		.author_id(ctx.author().id)
+		.timeout(THREAD_BUTTON_TIMEOUT)
+		.await;
+	// A message can only have one thread, so the button goes away either way. Errors are ignored
+	// in case the reply was deleted.
+	let _ = response
+		.edit(ctx, poise::CreateReply::default().components(Vec::new()))
+		.await;
+
+	if let Some(press) = press {
+		press.defer(ctx).await?;
+		post_output_in_thread(ctx, &press, &message, codeblock_lang, text).await?;
+	}
+
 	Ok(())
 }
 
+/// Posts the full output into a new thread started on the reply, split into code blocks. Where
+/// the bot can't create threads, the user is pointed to the Godbolt link instead.
+async fn post_output_in_thread(
+	ctx: Context<'_>,
+	press: &serenity::ComponentInteraction,
+	reply: &serenity::Message,
+	codeblock_lang: &str,
+	text: &str,
+) -> Result<(), Error> {
+	let can_create_threads = press.app_permissions.is_some_and(|permissions| {
+		permissions.create_public_threads() && permissions.send_messages_in_threads()
+	});
+
+	if can_create_threads {
+		let mut messages = crate::helpers::split_into_codeblocks(text, codeblock_lang);
+		if messages.len() > MAX_THREAD_MESSAGES {
+			messages.truncate(MAX_THREAD_MESSAGES);
+			messages.push("Output continues, see the Godbolt link for all of it".to_owned());
+		}
+
+		let thread_name = format!("Godbolt output for {}", ctx.author().name);
+		match crate::helpers::post_in_new_thread(ctx, reply, &thread_name, &messages).await {
+			Ok(_) => return Ok(()),
+			Err(e) => warn!("failed to post godbolt output in a thread: {}", e),
+		}
+	}
+
+	press
+		.create_followup(
+			ctx,
+			serenity::CreateInteractionResponseFollowup::new()
+				.content("Couldn't start a thread here, please use the Godbolt link instead")
+				.ephemeral(true),
+		)
+		.await?;
+	Ok(())
+}
+
+/// Substrings of symbols that belong to panic paths. These match both demangled and mangled
+/// (legacy and v0) symbol names, since all of them contain the path segments verbatim.
+const PANIC_PATH_MARKERS: &[&str] = &[
+	"panicking",
+	"panic_bounds_check",
+	"unwrap_failed",
+	"expect_failed",
+	"rust_begin_unwind",
+	"_Unwind_Resume",
+];
+
+/// Appends a marker comment to every assembly line that references a panic path, e.g. calls to
+/// `core::panicking::panic_bounds_check`. Returns the marked assembly and the number of marked
+/// lines.
+fn mark_panic_paths(asm: &str) -> (String, usize) {
+	let mut marked_lines = 0;
+	let mut output = String::with_capacity(asm.len());
+	for line in asm.lines() {
+		output.push_str(line);
+		if PANIC_PATH_MARKERS
+			.iter()
+			.any(|marker| line.contains(marker))
+		{
+			output.push_str("  ; <- panic path");
+			marked_lines += 1;
+		}
+		output.push('\n');
+	}
+	(output, marked_lines)
+}
+
+/// Returns the assembly of just the function with the given name, identified by its label, e.g.
+/// `example::math::square:` for `square`. Local labels like `.LBB0_1:` are part of the function.
+fn function_assembly(asm: &str, function_name: &str) -> Option<String> {
+	let is_function_label = |line: &str| {
+		!line.starts_with(char::is_whitespace) && !line.starts_with('.') && line.ends_with(':')
+	};
+
+	let mut lines = asm.lines().skip_while(|line| {
+		let Some(label) = line.strip_suffix(':') else {
+			return true;
+		};
+		!(is_function_label(line)
+			&& (label == function_name || label.ends_with(&format!("::{}", function_name))))
+	});
+	let label = lines.next()?;
+
+	let mut output = format!("{}\n", label);
+	for line in lines.take_while(|line| !is_function_label(line)) {
+		output += line;
+		output.push('\n');
+	}
+	Some(output)
+}
+
 /// View assembly using Godbolt
 ///
 /// Compile Rust code using <https://rust.godbolt.org>. Full optimizations are applied unless \
@@ -205,35 +362,412 @@ async fn respond_codeblock(
 /// ```
 /// Optional arguments:
 /// - `flags`: flags to pass to rustc invocation. Defaults to `"-Copt-level=3 --edition=2021"`
+/// - `preset`: common set of flags to use, overridable with `flags`. Possible values: `release` (`-Copt-level=3`), `size` (`-Copt-level=z -Clto=fat -Ccodegen-units=1`) or `native` (`-Copt-level=3 -Ctarget-cpu=native`)
+/// - `rustflags`: extra codegen flags from an allowlist, like `-Ctarget-feature=+avx2`
 /// - `rustc`: compiler version to invoke. Defaults to `nightly`. Possible values: `nightly`, `beta` or full version like `1.45.2`
+/// - `panics`: if `true`, marks instructions on panic paths (bounds checks, unwraps, ...)
+/// - `function`: name of the only function whose assembly to show
+///
+/// To split the code into modules, add more code blocks. Each becomes a module named by a \
+/// `// name.rs` comment on its first line, which is inlined where the first code block declares \
+/// `mod name;`.
+///
+/// Instead of including a code block, you can also reply to a message containing one.
 #[poise::command(prefix_command, category = "Godbolt", broadcast_typing, track_edits)]
 pub async fn godbolt(
 	ctx: Context<'_>,
 	params: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
+	modules: Vec<poise::CodeBlock>,
 ) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	let source_code = crate::helpers::assemble_modules(&code.code, &modules)?;
 	let (rustc, flags) = rustc_id_and_flags(ctx.data(), &params).await?;
 	let godbolt_request = GodboltRequest {
-		source_code: &code.code,
+		source_code: &source_code,
 		rustc: &rustc,
 		flags: &flags,
 		run_llvm_mca: false,
 	};
-	let godbolt_result = compile_rust_source(&ctx.data().http, &godbolt_request).await?;
+	let mut godbolt_result = compile_rust_source(ctx, &godbolt_request).await?;
 
-	let text =
-		crate::helpers::merge_output_and_errors(&godbolt_result.output, &godbolt_result.stderr);
-	let note = if code.code.contains("pub fn") {
-		"Note: only public functions (`pub fn`) are shown\n"
-	} else {
-		""
-	};
+	let mut note = preset_note(&params);
+	if source_code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown\n";
+	}
+	if let Some(function_name) = params.get("function").filter(|_| godbolt_result.success) {
+		godbolt_result.output =
+			function_assembly(&godbolt_result.output, function_name).ok_or(anyhow!(
+				"No assembly found for a function named `{}`. Is it `pub` and used?",
+				function_name
+			))?;
+	}
+	if godbolt_result.success && params.get("panics") == Some("true") {
+		let (marked_output, marked_lines) = mark_panic_paths(&godbolt_result.output);
+		godbolt_result.output = marked_output;
+		note += &match marked_lines {
+			0 => "No panic paths found\n".to_owned(),
+			n => format!(
+				"{} lines on panic paths are marked with `; <- panic path`\n",
+				n
+			),
+		};
+	}
+
+	let text = godbolt_result.text();
 	let codeblock_lang = if godbolt_result.success {
 		"x86asm"
 	} else {
 		"rust"
 	};
-	respond_codeblock(ctx, codeblock_lang, &text, note, &godbolt_request).await?;
+	respond_codeblock(ctx, codeblock_lang, &text, &note, &godbolt_request).await?;
+
+	Ok(())
+}
+
+/// Longest a section of a multi-section reply may be when all of them have to fit into one
+/// message
+const MAX_SECTION_LENGTH: usize = 800;
+const MAX_SECTION_LINES: usize = 20;
+
+/// One labelled code block of a reply that shows several outputs for the same snippet, like
+/// ?play-asm and ?asm-ir
+struct Section<'a> {
+	title: &'a str,
+	codeblock_lang: &'a str,
+	text: String,
+}
+
+/// Cuts the text down to fit into a section of a multi-section reply
+fn shorten_section(text: &str) -> String {
+	let lines = text.lines().collect::<Vec<_>>();
+	let mut shortened = lines
+		.iter()
+		.take(MAX_SECTION_LINES)
+		.copied()
+		.collect::<Vec<_>>()
+		.join("\n");
+	if shortened.chars().count() > MAX_SECTION_LENGTH {
+		shortened = shortened.chars().take(MAX_SECTION_LENGTH).collect();
+	} else if lines.len() <= MAX_SECTION_LINES {
+		return shortened;
+	}
+	shortened + "\n…"
+}
+
+/// Responds with the sections one below the other. If they don't fit into one message, the reply
+/// shows the start of each and all of them are posted in a thread, or, where the bot can't create
+/// threads, the reply links to Godbolt instead.
+async fn respond_sections(
+	ctx: Context<'_>,
+	sections: &[Section<'_>],
+	header: &str,
+	note: &str,
+	godbolt_request: &GodboltRequest<'_>,
+) -> Result<(), Error> {
+	let reply_text = |shorten: bool| {
+		let mut text = header.to_owned();
+		for section in sections {
+			let section_text = if shorten {
+				shorten_section(&section.text)
+			} else {
+				section.text.clone()
+			};
+			text += &format!(
+				"**{}**\n```{}\n{}\n```\n",
+				section.title,
+				section.codeblock_lang,
+				section_text.trim_end()
+			);
+		}
+		text + note
+	};
+
+	let full_text = reply_text(false);
+	if !crate::helpers::needs_trimming(&full_text, "") {
+		ctx.say(full_text).await?;
+		return Ok(());
+	}
+
+	let shortened_text = reply_text(true);
+	let reply = ctx
+		.say(format!("{}All of it is in the thread", shortened_text))
+		.await?;
+	let message = reply.message().await?.into_owned();
+
+	let mut messages = Vec::new();
+	for section in sections {
+		messages.push(format!("**{}**", section.title));
+		messages.extend(crate::helpers::split_into_codeblocks(
+			&section.text,
+			section.codeblock_lang,
+		));
+	}
+	if messages.len() > MAX_THREAD_MESSAGES {
+		messages.truncate(MAX_THREAD_MESSAGES);
+		messages.push("The rest was cut off to not flood the thread".to_owned());
+	}
+
+	let thread_name = format!("Godbolt output for {}", ctx.author().name);
+	if let Err(e) = crate::helpers::post_in_new_thread(ctx, &message, &thread_name, &messages).await
+	{
+		warn!("failed to post sections in a thread: {}", e);
+		reply
+			.edit(
+				ctx,
+				poise::CreateReply::default().content(format!(
+					"{}Godbolt link: <{}>",
+					shortened_text,
+					save_to_shortlink(&ctx.data().http, godbolt_request).await
+				)),
+			)
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// Params of ?play-asm that are meant for Godbolt rather than for the playground
+const PLAY_ASM_GODBOLT_PARAMS: &[&str] = &["flags", "preset", "rustflags", "rustc"];
+
+/// Run code and see its assembly at the same time
+///
+/// Runs the code on the Rust playground and compiles it on <https://rust.godbolt.org> at the \
+/// same time, and shows what the program prints together with what its public functions compile \
+/// to.
+/// ```
+/// ?play-asm mode={} channel={} edition={} flags={} rustc={} ``​`
+/// pub fn square(x: u32) -> u32 {
+///     x * x
+/// }
+///
+/// fn main() {
+///     println!("{}", square(3));
+/// }
+/// ``​`
+/// ```
+/// Optional arguments:
+/// - `mode`, `channel`, `edition`, `warn`: how to run the code, like for ?play
+/// - `flags`, `preset`, `rustflags`, `rustc`: how to compile the code, like for ?godbolt
+///
+/// If the output and the assembly don't fit into one message, they're posted in a thread.
+///
+/// Instead of including a code block, you can also reply to a message containing one.
+#[poise::command(
+	prefix_command,
+	rename = "play-asm",
+	aliases("playbolt"),
+	category = "Godbolt",
+	broadcast_typing,
+	track_edits
+)]
+pub async fn play_asm(
+	ctx: Context<'_>,
+	params: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	let (godbolt_params, play_flags): (std::collections::HashMap<_, _>, _) = params
+		.0
+		.into_iter()
+		.partition(|(key, _)| PLAY_ASM_GODBOLT_PARAMS.contains(&key.as_str()));
+	let godbolt_params = poise::KeyValueArgs(godbolt_params);
+	let (rustc, flags) = rustc_id_and_flags(ctx.data(), &godbolt_params).await?;
+	let godbolt_request = GodboltRequest {
+		source_code: &code.code,
+		rustc: &rustc,
+		flags: &flags,
+		run_llvm_mca: false,
+	};
+
+	let (play_result, compilation) = futures::future::join(
+		crate::commands::playground::run_snippet(ctx, &code.code, poise::KeyValueArgs(play_flags)),
+		compile_rust_source(ctx, &godbolt_request),
+	)
+	.await;
+
+	let (output, flag_parse_errors) = match play_result {
+		Ok(result) => result,
+		Err(e) => (format!("playground request failed: {}", e), String::new()),
+	};
+	let sections = [
+		Section {
+			title: "Output",
+			codeblock_lang: "rust",
+			text: output,
+		},
+		compilation_section("Assembly", "x86asm", compilation),
+	];
+
+	let mut note = preset_note(&godbolt_params);
+	if !code.code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown\n";
+	}
+	respond_sections(ctx, &sections, &flag_parse_errors, &note, &godbolt_request).await
+}
+
+/// Section showing the result of a Godbolt compilation, or why it failed
+fn compilation_section<'a>(
+	title: &'a str,
+	codeblock_lang: &'a str,
+	compilation: Result<Compilation, Error>,
+) -> Section<'a> {
+	match compilation {
+		Ok(compilation) if compilation.success => Section {
+			title,
+			codeblock_lang,
+			text: compilation.text().into_owned(),
+		},
+		Ok(compilation) => Section {
+			title,
+			codeblock_lang: "rust",
+			text: compilation.text().into_owned(),
+		},
+		Err(e) => Section {
+			title,
+			codeblock_lang: "",
+			text: format!("godbolt request failed: {}", e),
+		},
+	}
+}
+
+/// Returns the lines of the assembly that are instructions, i.e. not labels, directives or
+/// comments
+fn instruction_lines(asm: &str) -> Vec<&str> {
+	asm.lines()
+		.map(str::trim)
+		.filter(|line| {
+			!line.is_empty()
+				&& !line.ends_with(':')
+				&& !line.starts_with('.')
+				&& !line.starts_with(';')
+				&& !line.starts_with('#')
+		})
+		.collect()
+}
+
+/// Counts the instructions that were added to and removed from `baseline` to get `other`,
+/// ignoring where in the assembly they are
+fn instruction_diff(baseline: &[&str], other: &[&str]) -> (usize, usize) {
+	let mut counts = std::collections::HashMap::<&str, isize>::new();
+	for line in baseline {
+		*counts.entry(line).or_default() -= 1;
+	}
+	for line in other {
+		*counts.entry(line).or_default() += 1;
+	}
+
+	let added = counts.values().filter(|&&n| n > 0).sum::<isize>();
+	let removed = -counts.values().filter(|&&n| n < 0).sum::<isize>();
+	(added as usize, removed as usize)
+}
+
+/// Compare codegen across rustc versions
+///
+/// Compile Rust code on several compiler versions using <https://rust.godbolt.org> and summarize \
+/// how the assembly differs from the first version. Full optimizations are applied unless \
+/// overriden.
+/// ```
+/// ?godbolt-compare flags={} rustc={} ``​`
+/// pub fn your_function() {
+///     // Code
+/// }
+/// ``​`
+/// ```
+/// Optional arguments:
+/// - `flags`: flags to pass to rustc invocation. Defaults to `"-Copt-level=3 --edition=2021"`
+/// - `preset`: common set of flags to use, overridable with `flags`. Possible values: `release` (`-Copt-level=3`), `size` (`-Copt-level=z -Clto=fat -Ccodegen-units=1`) or `native` (`-Copt-level=3 -Ctarget-cpu=native`)
+/// - `rustflags`: extra codegen flags from an allowlist, like `-Ctarget-feature=+avx2`
+/// - `rustc`: comma-separated compiler versions to compare, at most 4. Defaults to the latest stable, `beta` and `nightly`
+///
+/// Instead of including a code block, you can also reply to a message containing one.
+#[poise::command(
+	prefix_command,
+	rename = "godbolt-compare",
+	category = "Godbolt",
+	broadcast_typing,
+	track_edits
+)]
+pub async fn godbolt_compare(
+	ctx: Context<'_>,
+	params: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	let rustc_ids = comparison_rustc_ids(ctx.data(), params.get("rustc")).await?;
+	let flags = &flags_from_params(&params)?;
+
+	let source_code = &code.code;
+	// Collected into owned ids up front: streaming over borrowed ones trips up the higher-ranked
+	// lifetime inference for the command future being Send
+	let ids = rustc_ids
+		.iter()
+		.map(|(_, rustc)| rustc.clone())
+		.collect::<Vec<_>>();
+	let compilations = futures::stream::iter(ids)
+		.map(|rustc| async move {
+			let godbolt_request = GodboltRequest {
+				source_code,
+				rustc: &rustc,
+				flags,
+				run_llvm_mca: false,
+			};
+			compile_rust_source(ctx, &godbolt_request).await
+		})
+		.buffered(MAX_CONCURRENT_COMPILATIONS)
+		.collect::<Vec<_>>()
+		.await;
+
+	let mut baseline: Option<(&String, Vec<&str>)> = None;
+	let mut table = String::new();
+	for ((version, _), compilation) in rustc_ids.iter().zip(&compilations) {
+		let summary = match compilation {
+			Err(e) => format!("godbolt request failed: {}", e),
+			Ok(compilation) if !compilation.success => {
+				match compilation
+					.stderr
+					.lines()
+					.find(|line| line.starts_with("error"))
+				{
+					Some(error) => format!("compilation failed: {}", error),
+					None => "compilation failed".to_owned(),
+				}
+			}
+			Ok(compilation) => {
+				let instructions = instruction_lines(&compilation.output);
+				let summary = match &baseline {
+					None => format!("{} instructions", instructions.len()),
+					Some((baseline_version, baseline_instructions)) => {
+						match instruction_diff(baseline_instructions, &instructions) {
+							(0, 0) => format!(
+								"{} instructions, identical to {}",
+								instructions.len(),
+								baseline_version
+							),
+							(added, removed) => format!(
+								"{} instructions, +{} -{} vs {}",
+								instructions.len(),
+								added,
+								removed,
+								baseline_version
+							),
+						}
+					}
+				};
+				if baseline.is_none() {
+					baseline = Some((version, instructions));
+				}
+				summary
+			}
+		};
+		table += &format!("{:<10} {}\n", version, summary);
+	}
+
+	let mut note = preset_note(&params);
+	if !code.code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown";
+	}
+	ctx.say(format!("```\n{}```\n{}", table, note)).await?;
 
 	Ok(())
 }
@@ -251,13 +785,18 @@ pub async fn godbolt(
 /// ```
 /// Optional arguments:
 /// - `flags`: flags to pass to rustc invocation. Defaults to `"-Copt-level=3 --edition=2021"`
+/// - `preset`: common set of flags to use, overridable with `flags`. Possible values: `release` (`-Copt-level=3`), `size` (`-Copt-level=z -Clto=fat -Ccodegen-units=1`) or `native` (`-Copt-level=3 -Ctarget-cpu=native`)
+/// - `rustflags`: extra codegen flags from an allowlist, like `-Ctarget-feature=+avx2`
 /// - `rustc`: compiler version to invoke. Defaults to `nightly`. Possible values: `nightly`, `beta` or full version like `1.45.2`
+///
+/// Instead of including a code block, you can also reply to a message containing one.
 #[poise::command(prefix_command, category = "Godbolt", broadcast_typing, track_edits)]
 pub async fn mca(
 	ctx: Context<'_>,
 	params: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 ) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
 	let (rustc, flags) = rustc_id_and_flags(ctx.data(), &params).await?;
 	let godbolt_request = GodboltRequest {
 		source_code: &code.code,
@@ -266,16 +805,14 @@ pub async fn mca(
 		run_llvm_mca: true,
 	};
 
-	let godbolt_result = compile_rust_source(&ctx.data().http, &godbolt_request).await?;
+	let godbolt_result = compile_rust_source(ctx, &godbolt_request).await?;
 
-	let text =
-		crate::helpers::merge_output_and_errors(&godbolt_result.output, &godbolt_result.stderr);
-	let note = if code.code.contains("pub fn") {
-		""
-	} else {
-		"Note: only public functions (`pub fn`) are shown"
-	};
-	respond_codeblock(ctx, "rust", &text, note, &godbolt_request).await?;
+	let text = godbolt_result.text();
+	let mut note = preset_note(&params);
+	if !code.code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown";
+	}
+	respond_codeblock(ctx, "rust", &text, &note, &godbolt_request).await?;
 
 	Ok(())
 }
@@ -295,13 +832,18 @@ pub async fn mca(
 /// ```
 /// Optional arguments:
 /// - `flags`: flags to pass to rustc invocation. Defaults to `"-Copt-level=3 --edition=2021"`
+/// - `preset`: common set of flags to use, overridable with `flags`. Possible values: `release` (`-Copt-level=3`), `size` (`-Copt-level=z -Clto=fat -Ccodegen-units=1`) or `native` (`-Copt-level=3 -Ctarget-cpu=native`)
+/// - `rustflags`: extra codegen flags from an allowlist, like `-Ctarget-feature=+avx2`
 /// - `rustc`: compiler version to invoke. Defaults to `nightly`. Possible values: `nightly`, `beta` or full version like `1.45.2`
+///
+/// Instead of including a code block, you can also reply to a message containing one.
 #[poise::command(prefix_command, category = "Godbolt", broadcast_typing, track_edits)]
 pub async fn llvmir(
 	ctx: Context<'_>,
 	params: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 ) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
 	let (rustc, flags) = rustc_id_and_flags(ctx.data(), &params).await?;
 	let godbolt_request = GodboltRequest {
 		source_code: &code.code,
@@ -309,21 +851,88 @@ pub async fn llvmir(
 		flags: &(flags + " --emit=llvm-ir -Cdebuginfo=0"),
 		run_llvm_mca: false,
 	};
-	let godbolt_result = compile_rust_source(&ctx.data().http, &godbolt_request).await?;
+	let godbolt_result = compile_rust_source(ctx, &godbolt_request).await?;
 
-	let text =
-		crate::helpers::merge_output_and_errors(&godbolt_result.output, &godbolt_result.stderr);
+	let text = godbolt_result.text();
 	let codeblock_lang = if godbolt_result.success {
 		"llvm"
 	} else {
 		"rust"
 	};
-	let note = if code.code.contains("pub fn") {
-		""
-	} else {
-		"Note: only public functions (`pub fn`) are shown"
-	};
-	respond_codeblock(ctx, codeblock_lang, &text, note, &godbolt_request).await?;
+	let mut note = preset_note(&params);
+	if !code.code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown";
+	}
+	respond_codeblock(ctx, codeblock_lang, &text, &note, &godbolt_request).await?;
 
 	Ok(())
 }
+
+/// View assembly and LLVM IR side by side
+///
+/// Compiles Rust code using <https://rust.godbolt.org> to both assembly and LLVM IR, to see how \
+/// the code is lowered step by step. Full optimizations are applied unless overriden.
+/// ```
+/// ?asm-ir flags={} rustc={} ``​`
+/// pub fn your_function() {
+///     // Code
+/// }
+/// ``​`
+/// ```
+/// Optional arguments:
+/// - `flags`: flags to pass to rustc invocation. Defaults to `"-Copt-level=3 --edition=2021"`
+/// - `preset`: common set of flags to use, overridable with `flags`. Possible values: `release` (`-Copt-level=3`), `size` (`-Copt-level=z -Clto=fat -Ccodegen-units=1`) or `native` (`-Copt-level=3 -Ctarget-cpu=native`)
+/// - `rustflags`: extra codegen flags from an allowlist, like `-Ctarget-feature=+avx2`
+/// - `rustc`: compiler version to invoke. Defaults to `nightly`. Possible values: `nightly`, `beta` or full version like `1.45.2`
+///
+/// If both don't fit into one message, they're posted in a thread.
+///
+/// Instead of including a code block, you can also reply to a message containing one.
+#[poise::command(
+	prefix_command,
+	rename = "asm-ir",
+	category = "Godbolt",
+	broadcast_typing,
+	track_edits
+)]
+pub async fn asm_ir(
+	ctx: Context<'_>,
+	params: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	let (rustc, flags) = rustc_id_and_flags(ctx.data(), &params).await?;
+	let asm_request = GodboltRequest {
+		source_code: &code.code,
+		rustc: &rustc,
+		flags: &flags,
+		run_llvm_mca: false,
+	};
+	let llvm_ir_request = GodboltRequest {
+		flags: &format!("{} --emit=llvm-ir -Cdebuginfo=0", flags),
+		..asm_request
+	};
+
+	let (asm, llvm_ir) = futures::future::join(
+		compile_rust_source(ctx, &asm_request),
+		compile_rust_source(ctx, &llvm_ir_request),
+	)
+	.await;
+
+	// If the code doesn't compile, both show the same errors
+	let sections = match &asm {
+		Ok(compilation) if !compilation.success => {
+			vec![compilation_section("Compilation failed", "rust", asm)]
+		}
+		_ => vec![
+			compilation_section("Assembly", "x86asm", asm),
+			compilation_section("LLVM IR", "llvm", llvm_ir),
+		],
+	};
+
+	let mut note = preset_note(&params);
+	if !code.code.contains("pub fn") {
+		note += "Note: only public functions (`pub fn`) are shown\n";
+	}
+	respond_sections(ctx, &sections, "", &note, &asm_request).await
+}
@@ -1,10 +1,16 @@
-use anyhow::{anyhow, Error};
+use anyhow::{anyhow, bail, Error};
 use poise::serenity_prelude as serenity;
 use tracing::{error, info};
 
 use crate::types::Context;
 use crate::types::Data;
 
+/// Flags passed to rustc when the user doesn't specify any
+pub(crate) const DEFAULT_FLAGS: &str = "-Copt-level=3 --edition=2021";
+
+/// The most rustc versions ?godbolt-compare compiles a snippet on
+const MAX_COMPARED_VERSIONS: usize = 4;
+
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct GodboltTarget {
@@ -50,7 +56,7 @@ impl GodboltTarget {
 }
 
 async fn update_godbolt_metadata(data: &Data) -> Result<(), Error> {
-	let last_update_time = data.godbolt_metadata.lock().unwrap().last_update_time;
+	let last_update_time = data.godbolt_metadata.read().await.last_update_time;
 	let needs_update = if let Some(last_update_time) = last_update_time {
 		// Get the time to wait between each update of the godbolt metadata
 		let update_period = std::env::var("GODBOLT_UPDATE_DURATION")
@@ -77,39 +83,53 @@ async fn update_godbolt_metadata(data: &Data) -> Result<(), Error> {
 		true
 	};
 
-	// If we should perform an update then do so
-	if needs_update {
-		let request = data
-			.http
-			.get("https://godbolt.org/api/compilers/rust")
-			.header(reqwest::header::ACCEPT, "application/json");
-		let mut targets: Vec<GodboltTarget> = request.send().await?.json().await?;
-		// Clean up the data we've gotten from the request
-		for target in &mut targets {
-			target.clean_request_data();
-			if let Some(semver) = target.semver.strip_prefix("rustc ") {
-				target.semver = semver.to_owned();
-			}
-		}
+	if !needs_update {
+		return Ok(());
+	}
 
-		let request = data
-			.http
-			.get("https://godbolt.org/api/libraries/rust")
-			.header(reqwest::header::ACCEPT, "application/json");
-		let libraries: Vec<GodboltLibrary> = request.send().await?.json().await?;
+	// Only one caller fetches at a time. The others keep using the outdated metadata meanwhile,
+	// unless there is none yet, in which case they wait for the fetch.
+	let _refresh_guard = match data.godbolt_metadata_refresh.try_lock() {
+		Ok(guard) => guard,
+		Err(_) if last_update_time.is_some() => return Ok(()),
+		Err(_) => data.godbolt_metadata_refresh.lock().await,
+	};
+	// Another caller may have finished fetching while this one was waiting
+	if data.godbolt_metadata.read().await.last_update_time != last_update_time {
+		return Ok(());
+	}
 
-		info!(
-			"updating godbolt metadata: {} targets, {} libraries",
-			targets.len(),
-			libraries.len()
-		);
-		*data.godbolt_metadata.lock().unwrap() = GodboltMetadata {
-			targets,
-			libraries,
-			last_update_time: Some(std::time::Instant::now()),
-		};
+	// The metadata is fetched without holding the lock, so that readers aren't blocked by it
+	let request = data
+		.http
+		.get("https://godbolt.org/api/compilers/rust")
+		.header(reqwest::header::ACCEPT, "application/json");
+	let mut targets: Vec<GodboltTarget> = request.send().await?.json().await?;
+	// Clean up the data we've gotten from the request
+	for target in &mut targets {
+		target.clean_request_data();
+		if let Some(semver) = target.semver.strip_prefix("rustc ") {
+			target.semver = semver.to_owned();
+		}
 	}
 
+	let request = data
+		.http
+		.get("https://godbolt.org/api/libraries/rust")
+		.header(reqwest::header::ACCEPT, "application/json");
+	let libraries: Vec<GodboltLibrary> = request.send().await?.json().await?;
+
+	info!(
+		"updating godbolt metadata: {} targets, {} libraries",
+		targets.len(),
+		libraries.len()
+	);
+	*data.godbolt_metadata.write().await = GodboltMetadata {
+		targets,
+		libraries,
+		last_update_time: Some(std::time::Instant::now()),
+	};
+
 	Ok(())
 }
 
@@ -121,7 +141,7 @@ pub async fn fetch_godbolt_metadata(
 		error!("failed to update godbolt metadata: {:?}", error);
 	}
 
-	data.godbolt_metadata.lock().unwrap()
+	data.godbolt_metadata.read().await
 }
 
 // Generates godbolt-compatible rustc identifier and flags from command input
@@ -143,14 +163,172 @@ pub(crate) async fn rustc_id_and_flags(
 			"the `rustc` argument should be a version specifier like `nightly` `beta` or `1.45.2`. \
             Run ?targets for a full list"))?;
 
-	let flags = params
-		.get("flags")
-		.unwrap_or("-Copt-level=3 --edition=2021")
-		.to_owned();
+	let flags = flags_from_params(params)?;
 
 	Ok((target.id, flags))
 }
 
+/// Named sets of rustc flags for common assembly inspection workflows
+const FLAG_PRESETS: &[(&str, &str)] = &[
+	("release", "-Copt-level=3"),
+	("size", "-Copt-level=z -Clto=fat -Ccodegen-units=1"),
+	("native", "-Copt-level=3 -Ctarget-cpu=native"),
+];
+
+fn find_preset(name: &str) -> Option<(&'static str, &'static str)> {
+	FLAG_PRESETS
+		.iter()
+		.find(|(preset_name, _)| *preset_name == name)
+		.copied()
+}
+
+/// Codegen options that the `rustflags` param may set, e.g. to explore SIMD codegen with
+/// `-Ctarget-feature=+avx2`. Anything else is rejected, so that these flags can't make rustc read
+/// or write arbitrary files or otherwise misbehave.
+const ALLOWED_RUSTFLAGS: &[&str] = &[
+	"code-model",
+	"codegen-units",
+	"debug-assertions",
+	"debuginfo",
+	"force-frame-pointers",
+	"lto",
+	"opt-level",
+	"overflow-checks",
+	"panic",
+	"relocation-model",
+	"target-cpu",
+	"target-feature",
+];
+
+/// Validates space-separated `-C<option>=<value>` flags against [`ALLOWED_RUSTFLAGS`], and
+/// normalizes them to that form. Like with rustc, the option may also be given as a separate
+/// token, as in `-C opt-level=3`.
+fn validate_rustflags(rustflags: &str) -> Result<String, Error> {
+	let mut tokens = rustflags.split_whitespace();
+	let mut validated = Vec::new();
+	while let Some(flag) = tokens.next() {
+		let option = match flag.strip_prefix("-C") {
+			Some("") => tokens
+				.next()
+				.ok_or_else(|| anyhow!("`-C` is missing a codegen option"))?,
+			Some(option) => option,
+			None => bail!(
+				"`{}` isn't a codegen flag, `rustflags` only takes `-C` flags",
+				flag
+			),
+		};
+		let (name, value) = option.split_once('=').unwrap_or((option, ""));
+		if !ALLOWED_RUSTFLAGS.contains(&name) {
+			bail!(
+				"`-C{}` isn't allowed in `rustflags`. Allowed are: {}",
+				name,
+				ALLOWED_RUSTFLAGS.join(", ")
+			);
+		}
+		if !value
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || "+-_,.".contains(c))
+		{
+			bail!("invalid value in `-C{}`", option);
+		}
+		validated.push(format!("-C{}", option));
+	}
+	Ok(validated.join(" "))
+}
+
+/// Resolves the `preset`, `flags` and `rustflags` params into the flags passed to rustc. Explicit
+/// flags come after the preset's, so they override it, and the validated `rustflags` come last.
+pub(crate) fn flags_from_params(params: &poise::KeyValueArgs) -> Result<String, Error> {
+	let flags = params.get("flags");
+	let mut flags = match params.get("preset") {
+		None => flags.unwrap_or(DEFAULT_FLAGS).to_owned(),
+		Some(preset) => {
+			let (_, preset_flags) = find_preset(preset).ok_or_else(|| {
+				anyhow!(
+					"unknown preset `{}`. Available presets: {}",
+					preset,
+					FLAG_PRESETS
+						.iter()
+						.map(|(name, _)| format!("`{}`", name))
+						.collect::<Vec<_>>()
+						.join(", ")
+				)
+			})?;
+			format!(
+				"{} {}",
+				validate_rustflags(preset_flags)?,
+				flags.unwrap_or("--edition=2021")
+			)
+		}
+	};
+
+	if let Some(rustflags) = params.get("rustflags") {
+		flags += " ";
+		flags += &validate_rustflags(rustflags)?;
+	}
+	Ok(flags)
+}
+
+/// Line for the output footer that documents what the `preset` param expanded to, if any
+pub(crate) fn preset_note(params: &poise::KeyValueArgs) -> String {
+	match params.get("preset").and_then(find_preset) {
+		Some((name, flags)) => format!("Preset `{}` expands to `{}`\n", name, flags),
+		None => String::new(),
+	}
+}
+
+/// Picks the godbolt compiler ids to compare codegen across, together with the version they were
+/// picked for. Versions can be given as a comma-separated list, otherwise the latest stable, beta
+/// and nightly are used.
+pub(crate) async fn comparison_rustc_ids(
+	data: &Data,
+	versions: Option<&str>,
+) -> Result<Vec<(String, String)>, Error> {
+	let metadata = fetch_godbolt_metadata(data).await;
+
+	let versions = match versions {
+		Some(versions) => versions
+			.split(',')
+			.map(|version| version.trim().to_owned())
+			.filter(|version| !version.is_empty())
+			.collect::<Vec<_>>(),
+		None => {
+			let latest_stable = metadata
+				.targets
+				.iter()
+				.map(|target| &*target.semver)
+				.filter(|semver| matches!(SemverRanking::from(*semver), SemverRanking::Semver(_)))
+				.min_by_key(|semver| SemverRanking::from(*semver));
+			latest_stable
+				.into_iter()
+				.chain(["beta", "nightly"])
+				.map(str::to_owned)
+				.collect()
+		}
+	};
+	if versions.is_empty() || versions.len() > MAX_COMPARED_VERSIONS {
+		bail!(
+			"the `rustc` argument should list between 1 and {} versions, like `1.45.2,nightly`",
+			MAX_COMPARED_VERSIONS
+		);
+	}
+
+	versions
+		.into_iter()
+		.map(|version| {
+			let target = metadata
+				.targets
+				.iter()
+				.find(|target| target.semver == version)
+				.ok_or(anyhow!(
+					"unknown rustc version `{}`. Run ?targets for a full list",
+					version
+				))?;
+			Ok((version, target.id.clone()))
+		})
+		.collect()
+}
+
 /// Used to rank godbolt compiler versions for listing them out
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 enum SemverRanking<'a> {
@@ -226,3 +404,57 @@ pub async fn targets(ctx: Context<'_>) -> Result<(), Error> {
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn accepts_allowed_rustflags() {
+		assert_eq!(
+			validate_rustflags("-Copt-level=3").unwrap(),
+			"-Copt-level=3"
+		);
+		assert_eq!(
+			validate_rustflags("-C opt-level=3").unwrap(),
+			"-Copt-level=3"
+		);
+		assert_eq!(
+			validate_rustflags("-Ctarget-feature=+avx2,+fma -C lto").unwrap(),
+			"-Ctarget-feature=+avx2,+fma -Clto"
+		);
+		assert_eq!(validate_rustflags("").unwrap(), "");
+	}
+
+	#[test]
+	fn rejects_other_rustflags() {
+		for rustflags in [
+			"-Clink-arg=-Wl,--version",
+			"-C link-arg=foo",
+			"-Cllvm-args=--x86-asm-syntax=intel",
+			"--emit=asm",
+			"-o /tmp/out",
+			"@file",
+			"-C",
+			"-Copt-level=3 -Zunpretty=mir",
+			"-Ctarget-cpu=../../etc/passwd",
+		] {
+			assert!(
+				validate_rustflags(rustflags).is_err(),
+				"`{}` was accepted",
+				rustflags
+			);
+		}
+	}
+
+	#[test]
+	fn presets_are_valid_rustflags() {
+		for (name, flags) in FLAG_PRESETS {
+			assert!(
+				validate_rustflags(flags).is_ok(),
+				"preset `{}` is invalid",
+				name
+			);
+		}
+	}
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{bail, Error};
+use poise::serenity_prelude as serenity;
+use tracing::warn;
+
+use crate::types::{Context, Data};
+
+/// How often the message counts collected in memory are written to the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+const DEFAULT_WINDOW_DAYS: u32 = 7;
+/// Older counts are deleted, see `database::message_counts`
+const MAX_WINDOW_DAYS: u32 = 90;
+
+const USERS_PER_PAGE: usize = 15;
+/// Most users shown on the leaderboard
+const MAX_RANKED_USERS: u32 = 100;
+
+/// Message counts collected since the last flush
+pub type PendingMessageCounts = std::sync::Arc<std::sync::Mutex<HashMap<serenity::UserId, u32>>>;
+
+/// Counts the message towards the author's tally, unless they opted out. This runs for every
+/// message, so it only touches memory; [`spawn_message_count_flusher`] writes the tallies to the
+/// database in batches.
+pub fn count_message(data: &Data, message: &serenity::Message) {
+	if message.author.bot
+		|| message.guild_id.is_none()
+		|| data
+			.leaderboard_opt_outs
+			.read()
+			.unwrap()
+			.contains(&message.author.id)
+	{
+		return;
+	}
+
+	*data
+		.pending_message_counts
+		.lock()
+		.unwrap()
+		.entry(message.author.id)
+		.or_default() += 1;
+}
+
+/// Periodically writes the message counts collected by [`count_message`] to the database
+pub fn spawn_message_count_flusher(data: &Data) {
+	let database = data.database.clone();
+	let pending_message_counts = data.pending_message_counts.clone();
+
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+		loop {
+			interval.tick().await;
+
+			let counts = std::mem::take(&mut *pending_message_counts.lock().unwrap());
+			if counts.is_empty() {
+				continue;
+			}
+			let counts = counts.into_iter().collect::<Vec<_>>();
+			if let Err(e) = crate::database::message_counts::add_to_today(&database, &counts).await
+			{
+				warn!("Couldn't flush message counts, retrying later: {:?}", e);
+				let mut pending = pending_message_counts.lock().unwrap();
+				for (user_id, count) in counts {
+					*pending.entry(user_id).or_default() += count;
+				}
+			}
+		}
+	});
+}
+
+/// Server activity rankings
+#[poise::command(
+	prefix_command,
+	slash_command,
+	subcommands("messages", "opt_out", "opt_in"),
+	subcommand_required,
+	category = "Utilities",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn leaderboard(_: Context<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Show the most active members by message count
+///
+/// /leaderboard messages [days]
+///
+/// Ranks the 100 members who sent the most messages in the last days (7 by default, at most \
+/// 90). Members can leave the ranking with `/leaderboard opt-out`.
+#[poise::command(prefix_command, slash_command, guild_only)]
+pub async fn messages(
+	ctx: Context<'_>,
+	#[description = "How many days to look back (default 7)"] days: Option<u32>,
+) -> Result<(), Error> {
+	let days = days.unwrap_or(DEFAULT_WINDOW_DAYS);
+	if days == 0 || days > MAX_WINDOW_DAYS {
+		bail!("The window must be between 1 and {} days", MAX_WINDOW_DAYS);
+	}
+
+	let counts =
+		crate::database::message_counts::top_users(&ctx.data().database, days, MAX_RANKED_USERS)
+			.await?;
+	if counts.is_empty() {
+		ctx.say("No messages were counted in that time yet.")
+			.await?;
+		return Ok(());
+	}
+
+	let lines = counts
+		.iter()
+		.enumerate()
+		.map(|(i, count)| format!("{}. <@{}>: {} messages", i + 1, count.user_id, count.count))
+		.collect::<Vec<_>>();
+	let pages = lines
+		.chunks(USERS_PER_PAGE)
+		.map(|page| {
+			format!(
+				"**Most active members, last {} days**\n{}",
+				days,
+				page.join("\n")
+			)
+		})
+		.collect::<Vec<_>>();
+
+	poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+
+	Ok(())
+}
+
+/// Stop counting your messages and remove you from the leaderboard
+#[poise::command(prefix_command, slash_command, ephemeral, rename = "opt-out")]
+pub async fn opt_out(ctx: Context<'_>) -> Result<(), Error> {
+	crate::database::message_counts::opt_out(&ctx.data().database, ctx.author().id).await?;
+	ctx.data().refresh_leaderboard_opt_outs().await?;
+	ctx.data()
+		.pending_message_counts
+		.lock()
+		.unwrap()
+		.remove(&ctx.author().id);
+
+	ctx.say("Your messages won't be counted anymore, and your existing counts were deleted.")
+		.await?;
+	Ok(())
+}
+
+/// Count your messages for the leaderboard again
+#[poise::command(prefix_command, slash_command, ephemeral, rename = "opt-in")]
+pub async fn opt_in(ctx: Context<'_>) -> Result<(), Error> {
+	crate::database::message_counts::opt_in(&ctx.data().database, ctx.author().id).await?;
+	ctx.data().refresh_leaderboard_opt_outs().await?;
+
+	ctx.say("Your messages will be counted for the leaderboard again.")
+		.await?;
+	Ok(())
+}
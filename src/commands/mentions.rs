@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use tracing::info;
+
+use crate::types::{Context, Data};
+
+/// Users in [`MentionDmMode::WhenIdle`] count as idle if they haven't sent a message for this long.
+/// Presence updates would be more accurate, but the presence intent is too expensive for a server
+/// this size.
+const IDLE_THRESHOLD: Duration = Duration::from_secs(15 * 60);
+
+/// Longest part of the mentioning message that's quoted in the DM
+const MAX_QUOTE_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum MentionDmMode {
+	#[name = "off"]
+	Off,
+	/// DM mentions while you haven't sent a message for a while
+	#[name = "when idle"]
+	WhenIdle,
+	/// DM all mentions until turned off again
+	#[name = "away"]
+	Away,
+}
+
+impl MentionDmMode {
+	/// Name stored in the database. [`Self::Off`] is represented by not having a row at all.
+	fn database_name(self) -> Option<&'static str> {
+		match self {
+			Self::Off => None,
+			Self::WhenIdle => Some("idle"),
+			Self::Away => Some("away"),
+		}
+	}
+
+	pub fn from_database_name(name: &str) -> Option<Self> {
+		match name {
+			"idle" => Some(Self::WhenIdle),
+			"away" => Some(Self::Away),
+			_ => None,
+		}
+	}
+}
+
+/// Returns whether the member can see the channel, going by the parent channel for threads
+pub(crate) fn can_view_channel(
+	ctx: &serenity::Context,
+	channel: &serenity::GuildChannel,
+	member: &serenity::Member,
+) -> bool {
+	let Some(guild) = ctx.cache.guild(channel.guild_id) else {
+		return false;
+	};
+	let channel = match channel.thread_metadata.and(channel.parent_id) {
+		Some(parent_id) => match guild.channels.get(&parent_id) {
+			Some(parent) => parent,
+			None => return false,
+		},
+		None => channel,
+	};
+	guild.user_permissions_in(channel, member).view_channel()
+}
+
+/// Records activity of opted-in users and DMs them about mentions they got while idle or away
+pub async fn handle_message(
+	ctx: &serenity::Context,
+	data: &Data,
+	message: &serenity::Message,
+) -> Result<(), Error> {
+	if message.author.bot || message.guild_id.is_none() {
+		return Ok(());
+	}
+
+	let recipients = {
+		let modes = data.mention_dm_modes.read().unwrap();
+		let mut last_activity = data.last_activity.lock().unwrap();
+		if modes.contains_key(&message.author.id) {
+			last_activity.insert(message.author.id, Instant::now());
+		}
+
+		message
+			.mentions
+			.iter()
+			.filter(|user| !user.bot && user.id != message.author.id)
+			.filter(|user| match modes.get(&user.id) {
+				Some(MentionDmMode::Away) => true,
+				Some(MentionDmMode::WhenIdle) => last_activity
+					.get(&user.id)
+					.is_none_or(|last_active| last_active.elapsed() >= IDLE_THRESHOLD),
+				Some(MentionDmMode::Off) | None => false,
+			})
+			.map(|user| user.id)
+			.collect::<Vec<_>>()
+	};
+	if recipients.is_empty() {
+		return Ok(());
+	}
+
+	let Some(channel) = message.channel(ctx).await?.guild() else {
+		return Ok(());
+	};
+
+	let quote = crate::helpers::quote_message(ctx, message, MAX_QUOTE_LENGTH);
+
+	for user_id in recipients {
+		// Mentions can reach people who can't see the channel, e.g. in replies to old messages
+		let Ok(member) = channel.guild_id.member(ctx, user_id).await else {
+			continue;
+		};
+		if !can_view_channel(ctx, &channel, &member) {
+			continue;
+		}
+
+		info!("Sending mention DM to {} for {}", user_id, message.id);
+		let embed = serenity::CreateEmbed::new()
+			.color(crate::types::EMBED_COLOR)
+			.title(format!(
+				"{} mentioned you in #{}",
+				message.author.name, channel.name
+			))
+			.description(format!(
+				"{}\n\n[Jump to the message]({})",
+				quote,
+				message.link()
+			))
+			.footer(serenity::CreateEmbedFooter::new(
+				"Turn these off with /mention-dms off",
+			))
+			.timestamp(message.timestamp);
+		crate::helpers::send_notification_dm(ctx, user_id, embed).await;
+	}
+
+	Ok(())
+}
+
+/// Get DMs about mentions while you're away
+///
+/// /mention-dms <mode>
+///
+/// Opts you into DMs about messages that mention you:
+/// - `when idle`: only while you haven't sent a message for 15 minutes
+/// - `away`: always, until you turn it off again
+/// - `off`: no DMs
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "mention-dms",
+	category = "Utilities",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn mention_dms(
+	ctx: Context<'_>,
+	#[description = "When to DM you about mentions"] mode: MentionDmMode,
+) -> Result<(), Error> {
+	let database = &ctx.data().database;
+	match mode.database_name() {
+		Some(name) => {
+			crate::database::mention_notifications::set(database, ctx.author().id, name).await?
+		}
+		None => crate::database::mention_notifications::remove(database, ctx.author().id).await?,
+	}
+	ctx.data().refresh_mention_dm_modes().await?;
+
+	ctx.say(match mode {
+		MentionDmMode::Off => "You won't get DMs about mentions anymore.",
+		MentionDmMode::WhenIdle => {
+			"You'll get DMs about mentions while you haven't sent a message for 15 minutes."
+		}
+		MentionDmMode::Away => {
+			"You'll get DMs about all mentions. Use `/mention-dms off` once you're back."
+		}
+	})
+	.await?;
+
+	Ok(())
+}
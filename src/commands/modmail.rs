@@ -1,7 +1,7 @@
-use anyhow::{anyhow, Error};
+use anyhow::{anyhow, bail, Error};
 use poise::serenity_prelude as serenity;
 use poise::serenity_prelude::{EditThread, Mentionable};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::types::{Context, Data};
 
@@ -23,7 +23,7 @@ pub async fn modmail_context_menu_for_message(
 		message.link_ensured(ctx).await,
 		message.content_safe(ctx)
 	);
-	create_modmail_thread(ctx, message).await?;
+	create_modmail_thread(ctx, message, false).await?;
 	Ok(())
 }
 
@@ -40,7 +40,7 @@ pub async fn modmail_context_menu_for_user(
 	#[description = "User to automatically link when opening a modmail"] user: serenity::User,
 ) -> Result<(), Error> {
 	let message = format!("User reported:\n{}\n{}\n\nPlease provide additional information about the user being reported.", user.id, user.name);
-	create_modmail_thread(ctx, message).await?;
+	create_modmail_thread(ctx, message, false).await?;
 	Ok(())
 }
 
@@ -57,18 +57,76 @@ pub async fn modmail_context_menu_for_user(
 /// dedicated modmail channel for moderators, and it allows them to deal with it much faster than if
 /// you were to DM a potentially AFK moderator.
 ///
+/// If you'd rather not reveal who you are, send the report anonymously. Moderators then only \
+/// learn your identity if they really need to, and you won't be added to the modmail thread. \
+/// Anonymous reports can only be sent with the slash command, where nobody sees the message.
+///
 /// You can still always ping the Moderator role if you're comfortable doing so.
 #[poise::command(prefix_command, slash_command, ephemeral, category = "Modmail")]
 pub async fn modmail(
 	ctx: Context<'_>,
 	#[description = "What would you like to say?"] user_message: String,
+	#[description = "Hide who you are from the moderators"] anonymous: Option<bool>,
 ) -> Result<(), Error> {
+	let anonymous = anonymous.unwrap_or(false);
+	// The invoking message of prefix commands is visible to everyone in the channel
+	if anonymous && matches!(ctx, poise::Context::Prefix(_)) {
+		bail!(
+			"Anonymous reports can only be sent with `/modmail`, since everyone can see this \
+			message. Consider deleting it."
+		);
+	}
+
 	let message = format!(
 		"{}\n\nSent from {}",
 		user_message,
 		ctx.channel_id().mention()
 	);
-	create_modmail_thread(ctx, message).await?;
+	create_modmail_thread(ctx, message, anonymous).await?;
+	Ok(())
+}
+
+/// Reveals who opened this modmail thread, even if they did so anonymously
+///
+/// /modmail-whois
+///
+/// Only use this for anonymous reports if it's really necessary. The reveal is noted in the thread.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "modmail-whois",
+	category = "Modmail",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn modmail_whois(ctx: Context<'_>) -> Result<(), Error> {
+	let reporter = crate::database::modmail_reporters::get(&ctx.data().database, ctx.channel_id())
+		.await?
+		.ok_or(anyhow!(
+			"This isn't a modmail thread, or it was opened before reporters were recorded"
+		))?;
+	let user_id = serenity::UserId::new(reporter.user_id as u64);
+
+	if reporter.anonymous {
+		ctx.channel_id()
+			.say(
+				ctx,
+				format!(
+					"{} looked up who sent this anonymous report.",
+					ctx.author().mention()
+				),
+			)
+			.await?;
+	}
+	ctx.say(format!(
+		"This modmail was opened by {} ({})",
+		user_id.mention(),
+		user_id
+	))
+	.await?;
+
 	Ok(())
 }
 
@@ -154,6 +212,7 @@ async fn store_message(data: &Data, message: serenity::Message) {
 async fn create_modmail_thread(
 	ctx: Context<'_>,
 	user_message: impl Into<String>,
+	anonymous: bool,
 ) -> Result<(), Error> {
 	load_or_create_modmail_message(ctx, ctx.data()).await?;
 
@@ -185,31 +244,91 @@ async fn create_modmail_thread(
 		.edit_thread(ctx, EditThread::new().invitable(false))
 		.await?;
 
-	let thread_message_content = format!(
-		"Hey {}, {} needs help with the following:\n> {}",
-		ctx.data().mod_role_id.mention(),
-		ctx.author().id.mention(),
-		user_message.into()
-	);
+	// The reporter is stored instead of shown for anonymous reports, see `modmail_whois`. Reports
+	// must go through even while the database is down, so failing to store them isn't fatal.
+	if let Err(e) = crate::database::modmail_reporters::insert(
+		&ctx.data().database,
+		modmail_thread.id,
+		ctx.author().id,
+		anonymous,
+	)
+	.await
+	{
+		warn!(
+			"Failed to store reporter {} of modmail thread {}: {:?}",
+			ctx.author().id,
+			modmail_thread.id,
+			e
+		);
+		if crate::database::database_error(&e).is_some_and(crate::database::is_connection_error) {
+			ctx.data().database_health.record_failure();
+		}
+	}
 
+	// The ping goes first and on its own, so moderators are notified even if the report is long.
+	// Mentioning the reporter adds them to the private thread, so anonymous reporters aren't.
+	let (ping_content, allowed_mentions) = if anonymous {
+		(
+			format!(
+				"Hey {}, someone needs help with the following. They chose to stay anonymous; \
+				use `?modmail-whois` only if you really need to know who they are:",
+				ctx.data().mod_role_id.mention(),
+			),
+			serenity::CreateAllowedMentions::new().roles([ctx.data().mod_role_id]),
+		)
+	} else {
+		(
+			format!(
+				"Hey {}, {} needs help with the following:",
+				ctx.data().mod_role_id.mention(),
+				ctx.author().id.mention(),
+			),
+			serenity::CreateAllowedMentions::new()
+				.users([ctx.author().id])
+				.roles([ctx.data().mod_role_id]),
+		)
+	};
 	modmail_thread
 		.send_message(
 			ctx,
 			serenity::CreateMessage::new()
-				.content(thread_message_content)
-				.allowed_mentions(
-					serenity::CreateAllowedMentions::new()
-						.users([ctx.author().id])
-						.roles([ctx.data().mod_role_id]),
-				),
+				.content(ping_content)
+				.allowed_mentions(allowed_mentions),
 		)
 		.await?;
 
-	ctx.say(format!(
-		"Successfully sent your message to the moderators. Check out your modmail thread here: {}",
-		modmail_thread.mention()
-	))
-	.await?;
+	let user_message = user_message.into();
+	let report_content = format!(">>> {}", user_message);
+	let was_truncated = crate::helpers::needs_trimming(&report_content, "");
+	let report_content = crate::helpers::trim_text(&report_content, "", async {
+		"\n_The report is too long to show here, see the attached file for all of it_".to_owned()
+	})
+	.await;
+
+	let mut report_message = serenity::CreateMessage::new()
+		.content(report_content)
+		.allowed_mentions(serenity::CreateAllowedMentions::new());
+	if was_truncated {
+		report_message = report_message.add_file(serenity::CreateAttachment::bytes(
+			user_message.into_bytes(),
+			"report.txt",
+		));
+	}
+	modmail_thread.send_message(ctx, report_message).await?;
+
+	if anonymous {
+		ctx.say(
+			"Successfully sent your anonymous message to the moderators. Since you're not part of \
+			the modmail thread, they can't reply to you there.",
+		)
+		.await?;
+	} else {
+		ctx.say(format!(
+			"Successfully sent your message to the moderators. Check out your modmail thread here: {}",
+			modmail_thread.mention()
+		))
+		.await?;
+	}
 
 	Ok(())
 }
@@ -0,0 +1,149 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error};
+use poise::serenity_prelude as serenity;
+use tracing::info;
+
+use crate::types::{Context, Data};
+
+const ACCEPT_COC_BUTTON_ID: &str = "rplcs_accept_coc";
+
+/// How new members get the Rustacean role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingMode {
+	/// Automatically, a while after joining
+	Timed,
+	/// Once they clicked "I agree" on the code of conduct message in the rules channel
+	Gated,
+}
+
+impl FromStr for OnboardingMode {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Error> {
+		match s {
+			"timed" => Ok(Self::Timed),
+			"gated" => Ok(Self::Gated),
+			_ => bail!(
+				"invalid onboarding mode `{}`, expected `timed` or `gated`",
+				s
+			),
+		}
+	}
+}
+
+pub async fn give_rustacean_role(
+	http: impl AsRef<serenity::Http>,
+	data: &Data,
+	user_id: serenity::UserId,
+	reason: &str,
+) -> Result<(), serenity::Error> {
+	http.as_ref()
+		.add_member_role(
+			data.discord_guild_id,
+			user_id,
+			data.rustacean_role_id,
+			Some(reason),
+		)
+		.await
+}
+
+/// Gives members that already accepted the code of conduct, e.g. before leaving and rejoining,
+/// the Rustacean role right away
+pub async fn handle_member_addition(
+	ctx: &serenity::Context,
+	data: &Data,
+	member: &serenity::Member,
+) -> Result<(), Error> {
+	if crate::database::coc_acceptances::has_accepted(&data.database, member.user.id).await? {
+		give_rustacean_role(
+			ctx,
+			data,
+			member.user.id,
+			"Rejoined after accepting the code of conduct",
+		)
+		.await?;
+	}
+	Ok(())
+}
+
+/// Handles clicks on the "I agree" button of the code of conduct message
+pub async fn handle_interaction(
+	ctx: &serenity::Context,
+	data: &Data,
+	interaction: &serenity::Interaction,
+) -> Result<(), Error> {
+	let serenity::Interaction::Component(press) = interaction else {
+		return Ok(());
+	};
+	if press.data.custom_id != ACCEPT_COC_BUTTON_ID {
+		return Ok(());
+	}
+
+	info!("{} accepted the code of conduct", press.user.name);
+	crate::database::coc_acceptances::accept(&data.database, press.user.id).await?;
+	give_rustacean_role(ctx, data, press.user.id, "Accepted the code of conduct").await?;
+
+	press
+		.create_response(
+			ctx,
+			serenity::CreateInteractionResponse::Message(
+				serenity::CreateInteractionResponseMessage::new()
+					.content("Thanks, and welcome! You now have access to the rest of the server.")
+					.ephemeral(true),
+			),
+		)
+		.await?;
+
+	Ok(())
+}
+
+/// Posts the code of conduct message with the "I agree" button in the rules channel
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn onboarding_setup(ctx: Context<'_>) -> Result<(), Error> {
+	let rules_channel_id = ctx
+		.data()
+		.rules_channel_id
+		.ok_or(anyhow!("No rules channel is configured"))?;
+
+	let message = rules_channel_id
+		.send_message(
+			ctx,
+			serenity::CreateMessage::new()
+				.content("\
+Welcome to the Rust Programming Language Community Server!
+
+Before you can chat, please read the rules above and the Rust Code of Conduct: <https://www.rust-lang.org/policies/code-of-conduct>
+
+By clicking the button below, you agree to follow both.")
+				.button(
+					serenity::CreateButton::new(ACCEPT_COC_BUTTON_ID)
+						.label("I agree")
+						.style(serenity::ButtonStyle::Success),
+				),
+		)
+		.await?;
+
+	let note = match ctx.data().onboarding_mode {
+		OnboardingMode::Gated => "",
+		OnboardingMode::Timed => {
+			"\nNote that new members also get the Rustacean role automatically, because the \
+			onboarding mode is `timed`."
+		}
+	};
+	ctx.say(format!(
+		"Posted the code of conduct message: {}{}",
+		message.link(),
+		note
+	))
+	.await?;
+
+	Ok(())
+}
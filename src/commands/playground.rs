@@ -1,13 +1,20 @@
 //! run rust code on the rust-lang playground
 
+pub use codesize::*;
 pub use microbench::*;
+pub use minimize::*;
 pub use misc_commands::*;
 pub use play_eval::*;
 pub use procmacro::*;
 
 mod api;
+mod codesize;
 mod microbench;
+mod minimize;
 mod misc_commands;
 mod play_eval;
 mod procmacro;
 mod util;
+
+pub(crate) use play_eval::run_snippet;
+pub(crate) use util::format_play_eval_stderr;
@@ -4,7 +4,7 @@ use std::str::FromStr;
 use anyhow::{anyhow, bail, Error};
 use reqwest::header;
 use serde::{Deserialize, Deserializer, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::types::Context;
 
@@ -14,6 +14,7 @@ pub struct CommandFlags {
 	pub edition: Edition,
 	pub warn: bool,
 	pub run: bool,
+	pub output: OutputMode,
 }
 
 #[derive(Debug, Serialize)]
@@ -54,6 +55,7 @@ pub struct FormatRequest<'a> {
 pub struct FormatResponse {
 	pub success: bool,
 	pub code: String,
+	#[allow(unused)]
 	pub stdout: String,
 	pub stderr: String,
 }
@@ -103,7 +105,6 @@ pub enum ProcessAssembly {
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
-#[allow(unused)]
 pub enum CompileTarget {
 	Mir,
 }
@@ -120,6 +121,16 @@ pub enum Channel {
 	Nightly,
 }
 
+impl Channel {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Channel::Stable => "stable",
+			Channel::Beta => "beta",
+			Channel::Nightly => "nightly",
+		}
+	}
+}
+
 impl FromStr for Channel {
 	type Err = Error;
 
@@ -143,6 +154,16 @@ pub enum Edition {
 	E2021,
 }
 
+impl Edition {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Edition::E2015 => "2015",
+			Edition::E2018 => "2018",
+			Edition::E2021 => "2021",
+		}
+	}
+}
+
 impl FromStr for Edition {
 	type Err = Error;
 
@@ -172,6 +193,15 @@ pub enum Mode {
 	Release,
 }
 
+impl Mode {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Mode::Debug => "debug",
+			Mode::Release => "release",
+		}
+	}
+}
+
 impl FromStr for Mode {
 	type Err = Error;
 
@@ -184,6 +214,30 @@ impl FromStr for Mode {
 	}
 }
 
+/// What to do with output that's too long for a Discord message
+#[derive(Debug, Clone, Copy)]
+pub enum OutputMode {
+	/// Truncate, and link to a playground gist with the code
+	Gist,
+	/// Truncate, and attach the full output as a text file
+	File,
+	/// Only truncate
+	Inline,
+}
+
+impl FromStr for OutputMode {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Error> {
+		match s {
+			"gist" => Ok(OutputMode::Gist),
+			"file" => Ok(OutputMode::File),
+			"inline" => Ok(OutputMode::Inline),
+			_ => bail!("invalid output mode `{}`", s),
+		}
+	}
+}
+
 #[derive(Debug)]
 pub struct PlayResult {
 	pub success: bool,
@@ -228,8 +282,49 @@ impl<'de> Deserialize<'de> for PlayResult {
 	}
 }
 
+/// Creates a gist directly through the GitHub API, authenticated with the given token
+async fn post_authenticated_gist(
+	http: &reqwest::Client,
+	github_token: &str,
+	code: &str,
+) -> Result<String, Error> {
+	#[derive(Deserialize)]
+	struct GistResponse {
+		id: String,
+	}
+
+	let resp = http
+		.post("https://api.github.com/gists")
+		.bearer_auth(github_token)
+		.header(header::USER_AGENT, "ferrisbot-for-discord")
+		.header(header::ACCEPT, "application/vnd.github+json")
+		.json(&serde_json::json!({
+			"description": "Code shared from the Rust Programming Language Community Discord",
+			"public": false,
+			"files": {
+				"playground.rs": { "content": code },
+			},
+		}))
+		.send()
+		.await?
+		.error_for_status()?;
+
+	Ok(resp.json::<GistResponse>().await?.id)
+}
+
 /// Returns a gist ID
+///
+/// If a GitHub token is configured, the gist is created with it, since anonymous gists through
+/// the playground are rate-limited aggressively. Otherwise, or if that fails, the playground
+/// creates the gist.
 pub async fn post_gist(ctx: Context<'_>, code: &str) -> Result<String, Error> {
+	if let Some(github_token) = &ctx.data().github_token {
+		match post_authenticated_gist(&ctx.data().http, github_token, code).await {
+			Ok(gist_id) => return Ok(gist_id),
+			Err(e) => warn!("failed to create authenticated gist, falling back: {}", e),
+		}
+	}
+
 	let mut payload = HashMap::new();
 	payload.insert("code", code);
 
@@ -261,11 +356,7 @@ pub fn url_from_gist(flags: &CommandFlags, gist_id: &str) -> String {
 			Mode::Debug => "debug",
 			Mode::Release => "release",
 		},
-		match flags.edition {
-			Edition::E2015 => "2015",
-			Edition::E2018 => "2018",
-			Edition::E2021 => "2021",
-		},
+		flags.edition.as_str(),
 		gist_id
 	)
 }
@@ -275,15 +366,18 @@ pub async fn apply_online_rustfmt(
 	code: &str,
 	edition: Edition,
 ) -> Result<PlayResult, Error> {
-	let result = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/format")
-		.json(&FormatRequest { code, edition })
-		.send()
-		.await?
-		.json::<FormatResponse>()
-		.await?;
+	let result = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/format")
+			.json(&FormatRequest { code, edition })
+			.send()
+			.await?
+			.json::<FormatResponse>()
+			.await?)
+	})
+	.await?;
 
 	Ok(PlayResult {
 		success: result.success,
@@ -0,0 +1,150 @@
+use anyhow::Error;
+
+use crate::types::Context;
+
+use super::{api::*, util::*};
+
+/// Report the binary size of one or two programs
+#[poise::command(
+	prefix_command,
+	track_edits,
+	help_text_fn = "codesize_help",
+	category = "Playground"
+)]
+pub async fn codesize(
+	ctx: Context<'_>,
+	flags: poise::KeyValueArgs,
+	code: poise::CodeBlock,
+	other_code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	ctx.say(stub_message(ctx)).await?;
+
+	let (flags, flag_parse_errors) = parse_flags(flags);
+
+	let mut snippets = String::new();
+	for code in std::iter::once(code).chain(other_code) {
+		snippets += &format!(
+			"r#####\"{}\"#####, ",
+			maybe_wrap(&code.code, ResultHandling::None)
+		);
+	}
+	let mut generated_code = format!(
+		"const EDITION: &str = \"{}\";\nconst SNIPPETS: &[&str] = &[{}];\n",
+		flags.edition.as_str(),
+		snippets
+	);
+	generated_code += r#"
+pub fn cmd_run(cmd: &str) -> bool {
+    std::process::Command::new("/bin/sh")
+        .args(&["-c", cmd])
+        .status()
+        .unwrap()
+        .success()
+}
+
+pub fn cmd_stdout(cmd: &str) -> String {
+    let output = std::process::Command::new("/bin/sh")
+        .args(&["-c", cmd])
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Builds the code as a stripped release binary and returns its size in bytes
+fn build(code: &str) -> std::io::Result<Option<u64>> {
+    std::env::set_current_dir(cmd_stdout("mktemp -d").trim())?;
+    if !cmd_run("cargo init -q --name codesize --bin") {
+        return Ok(None);
+    }
+    std::fs::write("src/main.rs", code)?;
+    let mut manifest = std::fs::read_to_string("Cargo.toml")?
+        .lines()
+        .map(|line| if line.starts_with("edition") { format!("edition = \"{}\"", EDITION) } else { line.to_owned() })
+        .collect::<Vec<_>>()
+        .join("\n");
+    manifest += "\n[profile.release]\nstrip = true\n";
+    std::fs::write("Cargo.toml", manifest)?;
+    if !cmd_run("cargo build -q --release") {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::metadata("target/release/codesize")?.len()))
+}
+
+fn main() -> std::io::Result<()> {
+    let mut sizes = Vec::new();
+    for (i, code) in SNIPPETS.iter().enumerate() {
+        let Some(size) = build(code)? else {
+            println!("Snippet {} failed to compile", i + 1);
+            std::process::exit(1);
+        };
+        println!("Snippet {}: {} bytes ({:.1} KiB)", i + 1, size, size as f64 / 1024.0);
+        sizes.push(size);
+    }
+    if let [before, after] = sizes[..] {
+        let delta = after as i64 - before as i64;
+        println!("Difference: {:+} bytes ({:+.2}%)", delta, delta as f64 * 100.0 / before as f64);
+    }
+    Ok(())
+}"#;
+
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code: &generated_code,
+				channel: Channel::Nightly,
+				// These flags only apply to the glue code, the snippets are always built in release mode
+				crate_type: CrateType::Binary,
+				edition: Edition::E2021,
+				mode: Mode::Debug,
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
+
+	// The glue code and the snippet builds both produce cargo output
+	result.stderr = format_play_eval_stderr(
+		&format_play_eval_stderr(&result.stderr, flags.warn),
+		flags.warn,
+	);
+
+	send_reply(
+		ctx,
+		result,
+		&generated_code,
+		&flags,
+		&flag_parse_errors,
+		false,
+	)
+	.await
+}
+
+pub fn codesize_help() -> String {
+	generic_help(GenericHelp {
+		command: "codesize",
+		desc: "\
+Builds the program as a stripped release binary and reports its size. Pass a second code block \
+to compare the size of two versions of a program, e.g. to see the impact of a change on a \
+`#![no_std]` binary",
+		mode_and_channel: false,
+		warn: true,
+		run: false,
+		reply: false,
+		example_code: "
+fn main() {
+    println!(\"Hello, world!\");
+}
+``\u{200B}` ``\u{200B}`
+fn main() {
+    std::hint::black_box(1 + 1);
+}
+",
+		examples: &["?codesize `fn main() { println!(\"Hello, world!\"); }` `fn main() {}`"],
+	})
+}
@@ -105,22 +105,25 @@ pub async fn microbench(
 	let code = hoise_crate_attributes(user_code, after_crate_attrs, &after_code);
 
 	let (flags, mut flag_parse_errors) = parse_flags(flags);
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/execute")
-		.json(&PlaygroundRequest {
-			code: &code,
-			channel: flags.channel,
-			crate_type: CrateType::Binary,
-			edition: flags.edition,
-			mode: Mode::Release, // benchmarks on debug don't make sense
-			tests: false,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code: &code,
+				channel: flags.channel,
+				crate_type: CrateType::Binary,
+				edition: flags.edition,
+				mode: Mode::Release, // benchmarks on debug don't make sense
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
 
 	result.stderr = format_play_eval_stderr(&result.stderr, flags.warn);
 
@@ -128,7 +131,7 @@ pub async fn microbench(
 		flag_parse_errors +=
 			"Hint: use the black_box function to prevent computations from being optimized out\n";
 	}
-	send_reply(ctx, result, &code, &flags, &flag_parse_errors).await
+	send_reply(ctx, result, &code, &flags, &flag_parse_errors, false).await
 }
 
 pub fn microbench_help() -> String {
@@ -146,6 +149,7 @@ that should be opaque to the optimizer: `number * 2` produces optimized integer
 		mode_and_channel: false,
 		warn: true,
 		run: false,
+		reply: false,
 		example_code: "
 pub fn add() {
     black_box(black_box(42.0) + black_box(99.0));
@@ -154,5 +158,8 @@ pub fn mul() {
     black_box(black_box(42.0) * black_box(99.0));
 }
 ",
+		examples: &[
+			"?microbench `pub fn sum() { black_box((0..black_box(1000u64)).sum::<u64>()); }`",
+		],
 	})
 }
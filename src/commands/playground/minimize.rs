@@ -0,0 +1,188 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Error};
+
+use crate::types::Context;
+
+use super::{api::CommandFlags, play_eval::execute, util::*};
+
+/// Most playground runs for one minimization. Every candidate snippet is one run.
+const MAX_RUNS: usize = 40;
+
+/// Time after which the minimization stops with the smallest snippet found so far
+const MAX_DURATION: Duration = Duration::from_secs(3 * 60);
+
+/// The progress message is updated after this many runs
+const PROGRESS_UPDATE_INTERVAL: usize = 5;
+
+/// Whether the brackets in the code are balanced. Candidates that aren't can't compile, so they're
+/// skipped without a run. Brackets in strings, chars and comments are counted as well, which is
+/// fine for a heuristic.
+fn brackets_balanced(lines: &[&str]) -> bool {
+	let mut stack = Vec::new();
+	for c in lines.iter().flat_map(|line| line.chars()) {
+		match c {
+			'(' | '[' | '{' => stack.push(c),
+			')' | ']' | '}' => {
+				let expected_opening = match c {
+					')' => '(',
+					']' => '[',
+					_ => '{',
+				};
+				if stack.pop() != Some(expected_opening) {
+					return false;
+				}
+			}
+			_ => {}
+		}
+	}
+	stack.is_empty()
+}
+
+/// Whether running the code still produces the expected error
+async fn reproduces(
+	ctx: Context<'_>,
+	lines: &[&str],
+	flags: &CommandFlags,
+	expected: &str,
+) -> Result<bool, Error> {
+	let code = lines.join("\n");
+	let code = maybe_wrapped(&code, ResultHandling::None, false, false);
+	let result = execute(ctx, &code, flags).await?;
+	Ok(result.stderr.contains(expected) || result.stdout.contains(expected))
+}
+
+/// Shrink failing code to a smaller snippet with the same error
+///
+/// A minimization takes up a compile slot for up to [`MAX_DURATION`], so only one runs at a time
+#[poise::command(
+	prefix_command,
+	broadcast_typing,
+	global_cooldown = 180,
+	help_text_fn = "minimize_help",
+	category = "Playground"
+)]
+pub async fn minimize(
+	ctx: Context<'_>,
+	mut flags: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let expected = flags.0.remove("expected").ok_or(anyhow!(
+		"Please give the error to keep with `expected`, like `expected=E0382`"
+	))?;
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	if !confirm_possible_secret(ctx, &code.code).await? {
+		return Ok(());
+	}
+	let (flags, flag_parse_errors) = parse_flags(flags);
+
+	let start_time = Instant::now();
+	let mut runs = 0;
+	let progress = ctx.say("Minimizing…").await?;
+
+	// Blank lines never matter
+	let mut lines = code
+		.code
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.collect::<Vec<_>>();
+	let original_line_count = lines.len();
+	runs += 1;
+	if !reproduces(ctx, &lines, &flags, &expected).await? {
+		bail!("The code doesn't produce `{}` to begin with", expected);
+	}
+
+	// Tries to remove ever smaller chunks of lines, keeping every removal that still reproduces the
+	// error, similar to delta debugging
+	let mut chunk_size = (lines.len() / 2).max(1);
+	let mut stopped_early = false;
+	'search: loop {
+		let mut removed_any = false;
+		let mut start = 0;
+		while start < lines.len() {
+			let end = (start + chunk_size).min(lines.len());
+			let candidate = [&lines[..start], &lines[end..]].concat();
+			if candidate.is_empty() || !brackets_balanced(&candidate) {
+				start += chunk_size;
+				continue;
+			}
+
+			if runs >= MAX_RUNS || start_time.elapsed() >= MAX_DURATION {
+				stopped_early = true;
+				break 'search;
+			}
+			if runs % PROGRESS_UPDATE_INTERVAL == 0 {
+				progress
+					.edit(
+						ctx,
+						poise::CreateReply::default().content(format!(
+							"Minimizing… {} runs so far, {} of {} lines left",
+							runs,
+							lines.len(),
+							original_line_count
+						)),
+					)
+					.await?;
+			}
+
+			runs += 1;
+			if reproduces(ctx, &candidate, &flags, &expected).await? {
+				lines = candidate;
+				removed_any = true;
+			} else {
+				start += chunk_size;
+			}
+		}
+
+		if !removed_any {
+			if chunk_size == 1 {
+				break;
+			}
+			chunk_size /= 2;
+		}
+	}
+
+	let mut summary = format!(
+		"{}Shrunk from {} to {} lines in {} runs, still producing `{}`",
+		flag_parse_errors,
+		original_line_count,
+		lines.len(),
+		runs,
+		expected
+	);
+	if stopped_early {
+		summary += ". Stopped early because of the limits, so it may get smaller still";
+	}
+	let text = crate::helpers::trim_text(
+		&format!("{}:\n```rust\n{}\n", summary, lines.join("\n")),
+		"```",
+		async { "Minimized code too long to show".to_owned() },
+	)
+	.await;
+	progress
+		.edit(ctx, poise::CreateReply::default().content(text))
+		.await?;
+
+	Ok(())
+}
+
+pub fn minimize_help() -> String {
+	crate::helpers::CommandHelp {
+		summary: "Shrinks code to a smaller snippet that still produces the same error, for \
+bug reports. All code is executed on https://play.rust-lang.org.",
+		usage: "?minimize expected={} mode={} channel={} edition={} ``\u{200B}`code``\u{200B}`",
+		details: "Lines are removed a chunk at a time, and every removal after which the \
+output still contains `expected` is kept. Give a specific error, like an error code or panic \
+message, so that the snippet doesn't shrink into a different error. The search stops after 40 \
+runs or three minutes, and only one minimization can run every three minutes.
+
+Optional arguments:
+- mode: debug, release (default: debug)
+- channel: stable, beta, nightly (default: nightly)
+- edition: 2015, 2018, 2021 (default: 2021)
+
+Instead of including a code block, you can also reply to a message containing one.",
+		examples: &["?minimize expected=E0382 ``\u{200B}`code``\u{200B}`"],
+	}
+	.render()
+}
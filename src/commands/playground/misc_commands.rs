@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use anyhow::Error;
+use anyhow::{bail, Error};
 use tracing::warn;
 
 use crate::types::Context;
@@ -28,18 +28,21 @@ pub async fn miri(
 	);
 	let (flags, flag_parse_errors) = parse_flags(flags);
 
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/miri")
-		.json(&MiriRequest {
-			code,
-			edition: flags.edition,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/miri")
+			.json(&MiriRequest {
+				code,
+				edition: flags.edition,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
 
 	result.stderr = extract_relevant_lines(
 		&result.stderr,
@@ -48,7 +51,7 @@ pub async fn miri(
 	)
 	.to_owned();
 
-	send_reply(ctx, result, code, &flags, &flag_parse_errors).await
+	send_reply(ctx, result, code, &flags, &flag_parse_errors, false).await
 }
 
 pub fn miri_help() -> String {
@@ -61,7 +64,9 @@ pub fn miri_help() -> String {
 		// warnings out
 		warn: false,
 		run: false,
+		reply: false,
 		example_code: "code",
+		examples: &["?miri `fn main() { let v = vec![1]; unsafe { v.get_unchecked(1); } }`"],
 	})
 }
 
@@ -83,18 +88,21 @@ pub async fn expand(
 	let was_fn_main_wrapped = matches!(code, Cow::Owned(_));
 	let (flags, flag_parse_errors) = parse_flags(flags);
 
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/macro-expansion")
-		.json(&MacroExpansionRequest {
-			code: &code,
-			edition: flags.edition,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/macro-expansion")
+			.json(&MacroExpansionRequest {
+				code: &code,
+				edition: flags.edition,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
 
 	result.stderr = extract_relevant_lines(
 		&result.stderr,
@@ -114,7 +122,7 @@ pub async fn expand(
 		result.stdout = strip_fn_main_boilerplate_from_formatted(&result.stdout);
 	}
 
-	send_reply(ctx, result, &code, &flags, &flag_parse_errors).await
+	send_reply(ctx, result, &code, &flags, &flag_parse_errors, false).await
 }
 
 pub fn expand_help() -> String {
@@ -124,7 +132,9 @@ pub fn expand_help() -> String {
 		mode_and_channel: false,
 		warn: false,
 		run: false,
+		reply: false,
 		example_code: "code",
+		examples: &["?expand `fn main() { println!(\"{:?}\", vec![1, 2]); }`"],
 	})
 }
 
@@ -155,19 +165,22 @@ pub async fn clippy(
 	);
 	let (flags, flag_parse_errors) = parse_flags(flags);
 
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/clippy")
-		.json(&ClippyRequest {
-			code,
-			edition: flags.edition,
-			crate_type: CrateType::Binary,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/clippy")
+			.json(&ClippyRequest {
+				code,
+				edition: flags.edition,
+				crate_type: CrateType::Binary,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
 
 	result.stderr = extract_relevant_lines(
 		&result.stderr,
@@ -181,7 +194,7 @@ pub async fn clippy(
 	)
 	.to_owned();
 
-	send_reply(ctx, result, code, &flags, &flag_parse_errors).await
+	send_reply(ctx, result, code, &flags, &flag_parse_errors, false).await
 }
 
 pub fn clippy_help() -> String {
@@ -191,7 +204,9 @@ pub fn clippy_help() -> String {
 		mode_and_channel: false,
 		warn: false,
 		run: false,
+		reply: false,
 		example_code: "code",
+		examples: &["?clippy `fn main() { let v = vec![1]; if v.len() == 0 {} }`"],
 	})
 }
 
@@ -219,7 +234,7 @@ pub async fn fmt(
 		result.stdout = strip_fn_main_boilerplate_from_formatted(&result.stdout);
 	}
 
-	send_reply(ctx, result, code, &flags, &flag_parse_errors).await
+	send_reply(ctx, result, code, &flags, &flag_parse_errors, false).await
 }
 
 pub fn fmt_help() -> String {
@@ -229,6 +244,117 @@ pub fn fmt_help() -> String {
 		mode_and_channel: false,
 		warn: false,
 		run: false,
+		reply: false,
 		example_code: "code",
+		examples: &["?fmt `fn main(){let x=1;println!(\"{}\",x)}`"],
 	})
 }
+
+/// Recovers the code from a sloppy paste: the contents of any fenced code blocks if there are
+/// some, otherwise the text with inline-code backticks around the whole paste or single lines
+/// removed
+fn extract_pasted_code(text: &str) -> String {
+	let text = text.trim();
+
+	if text.contains("```") {
+		let blocks = text
+			.split("```")
+			.skip(1)
+			.step_by(2)
+			.map(|block| match block.split_once('\n') {
+				// The first line is a language tag like ```rust, not code
+				Some((tag, code)) if !tag.trim().contains(char::is_whitespace) => code,
+				_ => block,
+			})
+			.map(|code| code.trim_matches('\n'))
+			.filter(|code| !code.trim().is_empty())
+			.collect::<Vec<_>>();
+		if !blocks.is_empty() {
+			return blocks.join("\n\n");
+		}
+	}
+
+	let text = text
+		.strip_prefix("``")
+		.and_then(|text| text.strip_suffix("``"))
+		.unwrap_or(text);
+	text.lines()
+		.map(|line| {
+			line.trim_end()
+				.strip_prefix('`')
+				.and_then(|line| line.strip_suffix('`'))
+				.unwrap_or(line)
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Repost pasted code as a proper code block
+///
+/// Takes code that was pasted without or with broken code block fencing (e.g. wrapped in \
+/// single backticks) and reposts it as a properly formatted ```rust code block. Either pass the \
+/// code directly or reply to the message containing it.
+/// ```
+/// ?clean-codeblocks fmt={} <code>
+/// ```
+/// Optional arguments:
+/// - `fmt`: if `true`, also formats the code using rustfmt
+#[poise::command(
+	prefix_command,
+	rename = "clean-codeblocks",
+	track_edits,
+	category = "Playground"
+)]
+pub async fn clean_codeblocks(
+	ctx: Context<'_>,
+	flags: poise::KeyValueArgs,
+	#[rest] text: Option<String>,
+) -> Result<(), Error> {
+	let text = match (text, ctx) {
+		(Some(text), _) => text,
+		(None, Context::Prefix(prefix_context)) => match &prefix_context.msg.referenced_message {
+			Some(referenced_message) => referenced_message.content.clone(),
+			None => bail!("Either include some code, or reply to a message containing it."),
+		},
+		(None, Context::Application(_)) => bail!("Missing code"),
+	};
+
+	let mut code = extract_pasted_code(&text);
+	if code.trim().is_empty() {
+		bail!("There's no code in there to clean up");
+	}
+	let mut note = "";
+	if flags.get("fmt") == Some("true") {
+		let wrapped = maybe_wrap(&code, ResultHandling::None);
+		let was_fn_main_wrapped = matches!(wrapped, Cow::Owned(_));
+		match apply_online_rustfmt(ctx, &wrapped, Edition::E2021).await {
+			Ok(PlayResult {
+				success: true,
+				stdout,
+				..
+			}) => {
+				code = if was_fn_main_wrapped {
+					strip_fn_main_boilerplate_from_formatted(&stdout)
+				} else {
+					stdout
+				};
+			}
+			Ok(_) => note = "\nrustfmt couldn't parse this code, so it's posted unformatted",
+			Err(e) => {
+				warn!("Couldn't run rustfmt: {}", e);
+				note = "\nCouldn't reach rustfmt, so the code is posted unformatted";
+			}
+		}
+	}
+
+	let text = crate::helpers::trim_text(
+		&format!("```rust\n{}", code.trim_end()),
+		&format!("\n```{}", note),
+		async { "Code too long to repost in full".to_owned() },
+	)
+	.await;
+	ctx.send(poise::CreateReply::default().content(text).reply(true))
+		.await?;
+
+	Ok(())
+}
@@ -9,9 +9,13 @@ async fn play_or_eval(
 	ctx: Context<'_>,
 	flags: poise::KeyValueArgs,
 	force_warnings: bool, // If true, force enable warnings regardless of flags
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 	result_handling: ResultHandling,
 ) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	if !confirm_possible_secret(ctx, &code.code).await? {
+		return Ok(());
+	}
 	ctx.say(stub_message(ctx)).await?;
 
 	let code = maybe_wrapped(
@@ -20,32 +24,59 @@ async fn play_or_eval(
 		ctx.prefix().contains("Sweat"),
 		ctx.prefix().contains("OwO") || ctx.prefix().contains("Cat"),
 	);
+	let flags = apply_edited_flags(ctx, flags).await;
 	let (mut flags, flag_parse_errors) = parse_flags(flags);
 
 	if force_warnings {
 		flags.warn = true;
 	}
 
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/execute")
-		.json(&PlaygroundRequest {
-			code: &code,
-			channel: flags.channel,
-			crate_type: CrateType::Binary,
-			edition: flags.edition,
-			mode: flags.mode,
-			tests: false,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result = execute(ctx, &code, &flags).await?;
+	result.stderr =
+		format_wrapped_play_eval_stderr(&result.stderr, flags.warn, &wrapper_line_numbers(&code));
 
-	result.stderr = format_play_eval_stderr(&result.stderr, flags.warn);
+	send_reply(ctx, result, &code, &flags, &flag_parse_errors, true).await
+}
 
-	send_reply(ctx, result, &code, &flags, &flag_parse_errors).await
+/// Runs the code as a binary on the playground
+pub(super) async fn execute(
+	ctx: Context<'_>,
+	code: &str,
+	flags: &CommandFlags,
+) -> Result<PlayResult, Error> {
+	crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code,
+				channel: flags.channel,
+				crate_type: CrateType::Binary,
+				edition: flags.edition,
+				mode: flags.mode,
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await
+}
+
+/// Runs the code like ?play does, for commands outside of the playground module. Returns the
+/// output formatted like ?play shows it, and the flag parse errors.
+pub(crate) async fn run_snippet(
+	ctx: Context<'_>,
+	code: &str,
+	flags: poise::KeyValueArgs,
+) -> Result<(String, String), Error> {
+	let (flags, flag_parse_errors) = parse_flags(flags);
+	let result = execute(ctx, code, &flags).await?;
+	let stderr = format_play_eval_stderr(&result.stderr, flags.warn);
+	let output = crate::helpers::merge_output_and_errors(&result.stdout, &stderr).into_owned();
+	Ok((output, flag_parse_errors))
 }
 
 /// Compile and run Rust code in a playground
@@ -58,7 +89,7 @@ async fn play_or_eval(
 pub async fn play(
 	ctx: Context<'_>,
 	flags: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 ) -> Result<(), Error> {
 	play_or_eval(ctx, flags, false, code, ResultHandling::None).await
 }
@@ -70,7 +101,12 @@ pub fn play_help() -> String {
 		mode_and_channel: true,
 		warn: true,
 		run: false,
+		reply: true,
 		example_code: "code",
+		examples: &[
+			"?play `fn main() { println!(\"Hello, world!\"); }`",
+			"?play mode=release channel=stable `fn main() { println!(\"{}\", u8::MAX); }`",
+		],
 	})
 }
 
@@ -84,7 +120,7 @@ category = "Playground"
 pub async fn playwarn(
 	ctx: Context<'_>,
 	flags: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 ) -> Result<(), Error> {
 	play_or_eval(ctx, flags, true, code, ResultHandling::None).await
 }
@@ -96,7 +132,9 @@ pub fn playwarn_help() -> String {
 		mode_and_channel: true,
 		warn: false,
 		run: false,
+		reply: true,
 		example_code: "code",
+		examples: &["?playwarn `fn main() { let unused = 1; }`"],
 	})
 }
 
@@ -110,7 +148,7 @@ pub fn playwarn_help() -> String {
 pub async fn eval(
 	ctx: Context<'_>,
 	flags: poise::KeyValueArgs,
-	code: poise::CodeBlock,
+	code: Option<poise::CodeBlock>,
 ) -> Result<(), Error> {
 	play_or_eval(ctx, flags, false, code, ResultHandling::Print).await
 }
@@ -122,6 +160,231 @@ pub fn eval_help() -> String {
 		mode_and_channel: true,
 		warn: true,
 		run: false,
+		reply: true,
+		example_code: "code",
+		examples: &[
+			"?eval `1 + 2`",
+			"?eval `\"hello\".chars().rev().collect::<String>()`",
+		],
+	})
+}
+
+/// Check whether Rust code compiles, without running it
+#[poise::command(
+	prefix_command,
+	track_edits,
+	aliases("compile-only"),
+	help_text_fn = "check_help",
+	category = "Playground"
+)]
+pub async fn check(
+	ctx: Context<'_>,
+	flags: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	if !confirm_possible_secret(ctx, &code.code).await? {
+		return Ok(());
+	}
+	ctx.say(stub_message(ctx)).await?;
+
+	let code = maybe_wrapped(
+		&code.code,
+		ResultHandling::None,
+		ctx.prefix().contains("Sweat"),
+		false,
+	);
+	let (flags, flag_parse_errors) = parse_flags(flags);
+
+	// Compiling to MIR goes through type and borrow checking like `cargo check`, but the code
+	// isn't run
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/compile")
+			.json(&CompileRequest {
+				assembly_flavor: AssemblyFlavour::default(),
+				backtrace: false,
+				channel: flags.channel,
+				code: &code,
+				crate_type: CrateType::Binary,
+				demangle_assembly: DemangleAssembly::default(),
+				edition: flags.edition,
+				mode: flags.mode,
+				process_assembly: ProcessAssembly::default(),
+				target: CompileTarget::Mir,
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
+
+	// Warnings are what this command is for, so they're always shown
+	result.stderr =
+		format_wrapped_play_eval_stderr(&result.stderr, true, &wrapper_line_numbers(&code));
+	result.stdout = if result.success {
+		"✅ Compiles successfully".to_owned()
+	} else {
+		String::new()
+	};
+
+	send_reply(ctx, result, &code, &flags, &flag_parse_errors, false).await
+}
+
+pub fn check_help() -> String {
+	generic_help(GenericHelp {
+		command: "check",
+		desc:
+			"Check whether Rust code compiles and show its errors and warnings, without running it",
+		mode_and_channel: true,
+		warn: false,
+		run: false,
+		reply: true,
 		example_code: "code",
+		examples: &["?check `fn main() { let x: u8 = \"one\"; }`"],
+	})
+}
+
+/// Compile and run the tests in Rust code
+#[poise::command(
+	prefix_command,
+	track_edits,
+	help_text_fn = "test_help",
+	category = "Playground"
+)]
+pub async fn test(
+	ctx: Context<'_>,
+	flags: poise::KeyValueArgs,
+	code: Option<poise::CodeBlock>,
+) -> Result<(), Error> {
+	let code = crate::helpers::code_block_or_replied(ctx, code).await?;
+	if !confirm_possible_secret(ctx, &code.code).await? {
+		return Ok(());
+	}
+	ctx.say(stub_message(ctx)).await?;
+
+	let (flags, flag_parse_errors) = parse_flags(flags);
+
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code: &code.code,
+				channel: flags.channel,
+				// Library, so that no `fn main` is required
+				crate_type: CrateType::Library,
+				edition: flags.edition,
+				mode: flags.mode,
+				tests: true,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
+
+	result.stderr = format_play_eval_stderr(&result.stderr, flags.warn);
+	if let Some(test_output) = format_test_output(&result.stdout) {
+		result.stdout = test_output;
+	}
+
+	send_reply(ctx, result, &code.code, &flags, &flag_parse_errors, false).await
+}
+
+pub fn test_help() -> String {
+	generic_help(GenericHelp {
+		command: "test",
+		desc: "Compile and run the tests (`#[test]` functions) in Rust code",
+		mode_and_channel: true,
+		warn: true,
+		run: false,
+		reply: true,
+		example_code: "
+#[test]
+fn it_works() {
+    assert_eq!(2 + 2, 4);
+}
+",
+		examples: &["?test `#[test] fn it_works() { assert_eq!(2 + 2, 4); }`"],
+	})
+}
+
+/// Compile and run Rust code and compare its output with the expected output
+#[poise::command(
+	prefix_command,
+	track_edits,
+	help_text_fn = "expect_help",
+	category = "Playground"
+)]
+pub async fn expect(
+	ctx: Context<'_>,
+	flags: poise::KeyValueArgs,
+	code: poise::CodeBlock,
+	expected_output: poise::CodeBlock,
+) -> Result<(), Error> {
+	ctx.say(stub_message(ctx)).await?;
+
+	let code = maybe_wrapped(
+		&code.code,
+		ResultHandling::None,
+		ctx.prefix().contains("Sweat"),
+		ctx.prefix().contains("OwO") || ctx.prefix().contains("Cat"),
+	);
+	let (flags, flag_parse_errors) = parse_flags(flags);
+
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code: &code,
+				channel: flags.channel,
+				crate_type: CrateType::Binary,
+				edition: flags.edition,
+				mode: flags.mode,
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
+
+	result.stderr =
+		format_wrapped_play_eval_stderr(&result.stderr, flags.warn, &wrapper_line_numbers(&code));
+	// If it didn't compile, there's no output to compare
+	if result.success || !result.stdout.is_empty() {
+		result.stdout = format_output_diff(&expected_output.code, &result.stdout);
+	}
+
+	send_reply(ctx, result, &code, &flags, &flag_parse_errors, false).await
+}
+
+pub fn expect_help() -> String {
+	generic_help(GenericHelp {
+		command: "expect",
+		desc: "Compile and run Rust code, and compare what it prints with the expected output \
+		given in a second code block. Trailing whitespace is ignored",
+		mode_and_channel: true,
+		warn: true,
+		run: false,
+		reply: false,
+		example_code: "
+fn main() {
+    println!(\"{}\", 0.1 + 0.2);
+}
+``\u{200B}` ``\u{200B}`
+0.3
+",
+		examples: &["?expect `fn main() { println!(\"{}\", 6 * 7); }` `42`"],
 	})
 }
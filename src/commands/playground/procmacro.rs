@@ -67,23 +67,26 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }"#;
 
-	let mut result: PlayResult = ctx
-		.data()
-		.http
-		.post("https://play.rust-lang.org/execute")
-		.json(&PlaygroundRequest {
-			code: &generated_code,
-			channel: Channel::Nightly, // so that inner proc macro gets nightly too
-			// These flags only apply to the glue code
-			crate_type: CrateType::Binary,
-			edition: Edition::E2021,
-			mode: Mode::Debug,
-			tests: false,
-		})
-		.send()
-		.await?
-		.json()
-		.await?;
+	let mut result: PlayResult = crate::helpers::with_compile_slot(ctx, async {
+		Ok(ctx
+			.data()
+			.http
+			.post("https://play.rust-lang.org/execute")
+			.json(&PlaygroundRequest {
+				code: &generated_code,
+				channel: Channel::Nightly, // so that inner proc macro gets nightly too
+				// These flags only apply to the glue code
+				crate_type: CrateType::Binary,
+				edition: Edition::E2021,
+				mode: Mode::Debug,
+				tests: false,
+			})
+			.send()
+			.await?
+			.json()
+			.await?)
+	})
+	.await?;
 
 	// funky
 	result.stderr = format_play_eval_stderr(
@@ -91,7 +94,15 @@ fn main() -> std::io::Result<()> {
 		flags.warn,
 	);
 
-	send_reply(ctx, result, &generated_code, &flags, &flag_parse_errors).await
+	send_reply(
+		ctx,
+		result,
+		&generated_code,
+		&flags,
+		&flag_parse_errors,
+		false,
+	)
+	.await
 }
 
 pub fn procmacro_help() -> String {
@@ -105,6 +116,7 @@ proc-macro code, and one for the usage code which can refer to the proc-macro cr
 		mode_and_channel: false,
 		warn: true,
 		run: true,
+		reply: false,
 		example_code: "
 #[proc_macro]
 pub fn foo(_: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -113,5 +125,6 @@ pub fn foo(_: proc_macro::TokenStream) -> proc_macro::TokenStream {
 ``\u{200B}` ``\u{200B}`
 procmacro::foo!();
 ",
+		examples: &[],
 	})
 }
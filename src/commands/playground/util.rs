@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 
+use once_cell::sync::Lazy;
 use poise::serenity_prelude as serenity;
-use serenity::ComponentInteraction;
+use serenity::{ComponentInteraction, Mentionable};
+use tracing::warn;
 
-use crate::types::Context;
+use crate::types::{Context, InvocationState};
 use crate::Error;
 
 use super::api;
@@ -30,6 +32,7 @@ pub fn parse_flags(mut args: poise::KeyValueArgs) -> (api::CommandFlags, String)
 		edition: api::Edition::E2021,
 		warn: false,
 		run: false,
+		output: api::OutputMode::Gist,
 	};
 
 	macro_rules! pop_flag {
@@ -48,6 +51,12 @@ pub fn parse_flags(mut args: poise::KeyValueArgs) -> (api::CommandFlags, String)
 	pop_flag!("edition", flags.edition);
 	pop_flag!("warn", flags.warn);
 	pop_flag!("run", flags.run);
+	pop_flag!("output", flags.output);
+
+	// The playground has no way to pass flags to rustc
+	if args.0.remove("rustflags").is_some() {
+		errors += "the playground doesn't support `rustflags`, try ?godbolt to see their effect on codegen\n";
+	}
 
 	for (remaining_flag, _) in args.0 {
 		errors += &format!("unknown flag `{}`\n", remaining_flag);
@@ -62,45 +71,58 @@ pub struct GenericHelp<'a> {
 	pub mode_and_channel: bool,
 	pub warn: bool,
 	pub run: bool,
+	/// Whether the code can also be taken from a replied-to message
+	pub reply: bool,
 	pub example_code: &'a str,
+	pub examples: &'a [&'a str],
 }
 
 pub fn generic_help(spec: GenericHelp<'_>) -> String {
-	let mut reply = format!(
-		"{}. All code is executed on https://play.rust-lang.org.\n",
+	let summary = format!(
+		"{}. All code is executed on https://play.rust-lang.org.",
 		spec.desc
 	);
 
-	reply += "```rust\n?";
-	reply += spec.command;
+	let mut usage = format!("?{}", spec.command);
 	if spec.mode_and_channel {
-		reply += " mode={} channel={}";
+		usage += " mode={} channel={}";
 	}
-	reply += " edition={}";
+	usage += " edition={}";
 	if spec.warn {
-		reply += " warn={}";
+		usage += " warn={}";
 	}
 	if spec.run {
-		reply += " run={}";
+		usage += " run={}";
 	}
-	reply += " ``\u{200B}`";
-	reply += spec.example_code;
-	reply += "``\u{200B}`\n```\n";
+	usage += " ``\u{200B}`";
+	usage += spec.example_code;
+	usage += "``\u{200B}`";
 
-	reply += "Optional arguments:\n";
+	let mut details = String::from("Optional arguments:\n");
 	if spec.mode_and_channel {
-		reply += "- mode: debug, release (default: debug)\n";
-		reply += "- channel: stable, beta, nightly (default: nightly)\n";
+		details += "- mode: debug, release (default: debug)\n";
+		details += "- channel: stable, beta, nightly (default: nightly)\n";
 	}
-	reply += "- edition: 2015, 2018, 2021 (default: 2021)\n";
+	details += "- edition: 2015, 2018, 2021 (default: 2021)\n";
 	if spec.warn {
-		reply += "- warn: true, false (default: false)\n";
+		details += "- warn: true, false (default: false)\n";
 	}
 	if spec.run {
-		reply += "- run: true, false (default: false)\n";
+		details += "- run: true, false (default: false)\n";
 	}
+	details += "- output: what to do with long output; gist, file, inline (default: gist)\n";
 
-	reply
+	if spec.reply {
+		details += "\nInstead of including a code block, you can also reply to a message containing one.\n";
+	}
+
+	crate::helpers::CommandHelp {
+		summary: &summary,
+		usage: &usage,
+		details: &details,
+		examples: spec.examples,
+	}
+	.render()
 }
 
 /// Strip the input according to a list of start tokens and end tokens. Everything after the start
@@ -197,7 +219,7 @@ pub fn hoise_crate_attributes(code: &str, after_crate_attrs: &str, after_code: &
 /// Utility used by the commands to wrap the given code in a `fn main` if not already wrapped.
 /// To check, whether a wrap was done, check if the return type is Cow::Borrowed vs Cow::Owned
 /// If a wrap was done, also hoists crate attributes to the top so they keep working
-pub fn maybe_wrap(code: &str, result_handling: ResultHandling) -> Cow<str> {
+pub fn maybe_wrap(code: &str, result_handling: ResultHandling) -> Cow<'_, str> {
 	maybe_wrapped(code, result_handling, false, false)
 }
 
@@ -205,12 +227,12 @@ pub fn maybe_wrapped(
 	code: &str,
 	result_handling: ResultHandling,
 	unsf: bool,
-	pretty: bool,
-) -> Cow<str> {
-	use quote::quote;
+	_pretty: bool,
+) -> Cow<'_, str> {
 	use syn::{parse::Parse, *};
 
 	// We use syn to check whether there is a main function.
+	#[allow(unused)]
 	struct Inline {
 		attrs: Vec<Attribute>,
 		stmts: Vec<Stmt>,
@@ -269,13 +291,15 @@ pub fn maybe_wrapped(
 	))
 }
 
-/// Send a Discord reply with the formatted contents of a Playground result
+/// Send a Discord reply with the formatted contents of a Playground result. With `editable_flags`,
+/// the reply gets a button to change the mode, channel and edition and run the code again.
 pub async fn send_reply(
 	ctx: Context<'_>,
 	result: api::PlayResult,
 	code: &str,
 	flags: &api::CommandFlags,
 	flag_parse_errors: &str,
+	editable_flags: bool,
 ) -> Result<(), Error> {
 	let result = crate::helpers::merge_output_and_errors(&result.stdout, &result.stderr);
 
@@ -294,56 +318,384 @@ pub async fn send_reply(
 		text_end += "Playground timeout detected";
 	}
 
-	let text = crate::helpers::trim_text(
-		&format!("{}```rust\n{}", flag_parse_errors, result),
-		&text_end,
-		async {
-			format!(
+	let text_body = format!("{}```rust\n{}", flag_parse_errors, result);
+	// Gists are public, so they're never created for code that may contain a secret
+	let attach_full_output = crate::helpers::needs_trimming(&text_body, &text_end)
+		&& match flags.output {
+			api::OutputMode::Gist => find_possible_secret(code).is_some(),
+			api::OutputMode::File => true,
+			api::OutputMode::Inline => false,
+		};
+	let text = crate::helpers::trim_text(&text_body, &text_end, async {
+		match flags.output {
+			_ if attach_full_output => {
+				"Output too large, see the attached file for all of it".to_owned()
+			}
+			api::OutputMode::Gist => format!(
 				"Output too large. Playground link: <{}>",
 				api::url_from_gist(flags, &api::post_gist(ctx, code).await.unwrap_or_default()),
-			)
-		},
-	)
+			),
+			api::OutputMode::File | api::OutputMode::Inline => "Output too large".to_owned(),
+		}
+	})
 	.await;
 
-	let custom_id = ctx.id().to_string();
+	let retry_id = format!("{}retry", ctx.id());
+	let share_id = format!("{}share", ctx.id());
+	let edit_flags_id = format!("{}editflags", ctx.id());
+	let rerun_id = format!("{}rerun", ctx.id());
+	// All buttons but the share button, which goes away once used
+	let other_buttons = || {
+		let mut buttons = Vec::new();
+		if timeout {
+			buttons.push(
+				serenity::CreateButton::new(&retry_id)
+					.label("Retry")
+					.style(serenity::ButtonStyle::Primary),
+			);
+		}
+		if editable_flags {
+			buttons.push(
+				serenity::CreateButton::new(&edit_flags_id)
+					.label("Edit flags")
+					.style(serenity::ButtonStyle::Secondary),
+			);
+		}
+		buttons
+	};
 
 	let response = ctx
 		.send({
 			let mut b = poise::CreateReply::default().content(text);
-			if timeout {
-				b = b.components(vec![serenity::CreateActionRow::Buttons(vec![
-					serenity::CreateButton::new(&custom_id)
-						.label("Retry")
-						.style(serenity::ButtonStyle::Primary),
-				])]);
+			if attach_full_output {
+				b = b.attachment(serenity::CreateAttachment::bytes(
+					result.as_bytes().to_vec(),
+					"output.txt",
+				));
 			}
-			b
+			let mut buttons = other_buttons();
+			buttons.push(
+				serenity::CreateButton::new(&share_id)
+					.label("Share to thread")
+					.style(serenity::ButtonStyle::Secondary),
+			);
+			b.components(vec![serenity::CreateActionRow::Buttons(buttons)])
 		})
 		.await?;
+	// This keeps running for a while to handle button presses, so don't wait for that
+	crate::commands::auto_delete::delete_invocation_if_configured(ctx).await;
+	let message = response.message().await?.into_owned();
 
-	if let Some(retry_pressed) = response
-		.message()
-		.await?
-		.await_component_interaction(ctx)
-		.filter(move |mci: &ComponentInteraction| mci.data.custom_id == custom_id)
+	let mut edited_flags = InvocationState::get(ctx).await.edited_flags;
+	// The press that opened the flag editor, to close the editor on timeout
+	let mut flag_editor = None;
+
+	let ctx_id = ctx.id().to_string();
+	// Not bound to the reply, because the flag editor is a separate, ephemeral message
+	while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+		.filter({
+			let ctx_id = ctx_id.clone();
+			move |mci: &ComponentInteraction| {
+				mci.data
+					.custom_id
+					.strip_prefix(&ctx_id)
+					.is_some_and(|action| !action.starts_with(|c: char| c.is_ascii_digit()))
+			}
+		})
 		.timeout(std::time::Duration::from_secs(600))
 		.await
 	{
-		retry_pressed.defer(&ctx).await?;
-		ctx.rerun().await?;
-	} else {
-		// If timed out, just remove the button
-		// Errors are ignored in case the reply was deleted
-		let _ = response
-			// TODO: Add code to remove button
-			.edit(ctx, poise::CreateReply::default())
+		if press.data.custom_id == retry_id {
+			press.defer(&ctx).await?;
+			ctx.rerun().await?;
+			return Ok(());
+		}
+
+		if press.user.id != ctx.author().id {
+			press
+				.create_response(
+					ctx,
+					serenity::CreateInteractionResponse::Message(
+						serenity::CreateInteractionResponseMessage::new()
+							.content("Only the author of this snippet can do that")
+							.ephemeral(true),
+					),
+				)
+				.await?;
+			continue;
+		}
+
+		if press.data.custom_id == share_id {
+			press.defer(&ctx).await?;
+			share_to_thread(ctx, &press, &message, code, &result).await?;
+
+			// A message can only have one thread, so sharing is a one-time thing
+			let buttons = other_buttons();
+			response
+				.edit(
+					ctx,
+					poise::CreateReply::default().components(if buttons.is_empty() {
+						Vec::new()
+					} else {
+						vec![serenity::CreateActionRow::Buttons(buttons)]
+					}),
+				)
+				.await?;
+		} else if press.data.custom_id == edit_flags_id {
+			press
+				.create_response(
+					ctx,
+					serenity::CreateInteractionResponse::Message(
+						serenity::CreateInteractionResponseMessage::new()
+							.content("Choose the flags to run the code with:")
+							.components(flag_editor_components(&ctx_id, flags))
+							.ephemeral(true),
+					),
+				)
+				.await?;
+			flag_editor = Some(press);
+		} else if press.data.custom_id == rerun_id {
+			press
+				.create_response(
+					ctx,
+					serenity::CreateInteractionResponse::UpdateMessage(
+						serenity::CreateInteractionResponseMessage::new()
+							.content("Running the code again with the new flags…")
+							.components(Vec::new()),
+					),
+				)
+				.await?;
+			InvocationState::update(ctx, |state| state.edited_flags = edited_flags).await;
+			ctx.rerun().await?;
+			return Ok(());
+		} else if let serenity::ComponentInteractionDataKind::StringSelect { values } =
+			&press.data.kind
+		{
+			// The custom IDs of the select menus are the invocation ID followed by the flag name
+			if let (Some(flag), Some(value)) =
+				(press.data.custom_id.strip_prefix(&ctx_id), values.first())
+			{
+				edited_flags.insert(flag.to_owned(), value.clone());
+			}
+			press
+				.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+				.await?;
+		}
+	}
+
+	// If timed out, just remove the buttons
+	// Errors are ignored in case the reply was deleted
+	let _ = response
+		.edit(ctx, poise::CreateReply::default().components(vec![]))
+		.await;
+	if let Some(flag_editor) = flag_editor {
+		let _ = flag_editor
+			.edit_response(
+				ctx,
+				serenity::EditInteractionResponse::new()
+					.content("The flag editor timed out")
+					.components(Vec::new()),
+			)
 			.await;
 	}
 
 	Ok(())
 }
 
+/// Applies the flags chosen in the flag editor of a previous run of this invocation, if any, on
+/// top of the ones given in the message
+pub async fn apply_edited_flags(
+	ctx: Context<'_>,
+	mut flags: poise::KeyValueArgs,
+) -> poise::KeyValueArgs {
+	flags.0.extend(InvocationState::get(ctx).await.edited_flags);
+	flags
+}
+
+/// Select menus for the mode, channel and edition, with the current flags preselected, and a
+/// button to run the code again
+fn flag_editor_components(
+	ctx_id: &str,
+	flags: &api::CommandFlags,
+) -> Vec<serenity::CreateActionRow> {
+	let select_menu = |flag: &str, values: &[&str], current: &str| {
+		serenity::CreateActionRow::SelectMenu(serenity::CreateSelectMenu::new(
+			format!("{}{}", ctx_id, flag),
+			serenity::CreateSelectMenuKind::String {
+				options: values
+					.iter()
+					.map(|&value| {
+						serenity::CreateSelectMenuOption::new(format!("{}: {}", flag, value), value)
+							.default_selection(value == current)
+					})
+					.collect(),
+			},
+		))
+	};
+
+	vec![
+		select_menu("mode", &["debug", "release"], flags.mode.as_str()),
+		select_menu(
+			"channel",
+			&["stable", "beta", "nightly"],
+			flags.channel.as_str(),
+		),
+		select_menu("edition", &["2015", "2018", "2021"], flags.edition.as_str()),
+		serenity::CreateActionRow::Buttons(vec![serenity::CreateButton::new(format!(
+			"{}rerun",
+			ctx_id
+		))
+		.label("Run")
+		.style(serenity::ButtonStyle::Primary)]),
+	]
+}
+
+/// Well-known kinds of secrets, described for the warning
+static SECRET_PATTERNS: Lazy<Vec<(&str, regex::Regex)>> = Lazy::new(|| {
+	[
+		(
+			"a Discord token",
+			r"[MNO][A-Za-z\d_-]{23,27}\.[A-Za-z\d_-]{6}\.[A-Za-z\d_-]{27,40}",
+		),
+		("an AWS access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+		(
+			"a GitHub token",
+			r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+		),
+		("a Google API key", r"\bAIza[0-9A-Za-z_-]{35}"),
+		("a Slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+		("a private key", r"-----BEGIN (?:[A-Z]+ )?PRIVATE KEY-----"),
+	]
+	.into_iter()
+	.map(|(kind, pattern)| (kind, regex::Regex::new(pattern).unwrap()))
+	.collect()
+});
+
+/// Returns what kind of secret the code seems to contain, if any
+pub fn find_possible_secret(code: &str) -> Option<&'static str> {
+	SECRET_PATTERNS
+		.iter()
+		.find(|(_, pattern)| pattern.is_match(code))
+		.map(|(kind, _)| *kind)
+}
+
+/// If the code looks like it contains a secret, asks the author whether to run it anyway. Returns
+/// whether to go ahead.
+pub async fn confirm_possible_secret(ctx: Context<'_>, code: &str) -> Result<bool, Error> {
+	let Some(kind) = find_possible_secret(code) else {
+		return Ok(true);
+	};
+	// Reruns don't ask again
+	if InvocationState::get(ctx).await.secret_confirmed {
+		return Ok(true);
+	}
+
+	let run_id = format!("{}secretrun", ctx.id());
+	let cancel_id = format!("{}secretcancel", ctx.id());
+	let reply = ctx
+		.send(
+			poise::CreateReply::default()
+				.content(format!(
+					"This code looks like it contains {}. If it's a real one, revoke it, since \
+					anyone in this channel can see it. Run the code anyway?",
+					kind
+				))
+				.components(vec![serenity::CreateActionRow::Buttons(vec![
+					serenity::CreateButton::new(&run_id)
+						.label("Run anyway")
+						.style(serenity::ButtonStyle::Danger),
+					serenity::CreateButton::new(&cancel_id)
+						.label("Cancel")
+						.style(serenity::ButtonStyle::Secondary),
+				])]),
+		)
+		.await?;
+
+	let press = reply
+		.message()
+		.await?
+		.await_component_interaction(ctx)
+		.author_id(ctx.author().id)
+		.timeout(std::time::Duration::from_secs(60))
+		.await;
+	let confirmed = match &press {
+		Some(press) => {
+			press.defer(ctx).await?;
+			press.data.custom_id == run_id
+		}
+		None => false,
+	};
+
+	if confirmed {
+		InvocationState::update(ctx, |state| state.secret_confirmed = true).await;
+	}
+	reply
+		.edit(
+			ctx,
+			poise::CreateReply::default()
+				.content(if confirmed {
+					"Running the code…"
+				} else {
+					"Not running the code."
+				})
+				.components(Vec::new()),
+		)
+		.await?;
+
+	Ok(confirmed)
+}
+
+/// Archives the code and its full output into a new thread started on the reply, attributed to
+/// the author. Where the bot can't create threads, the code is posted as a file instead.
+async fn share_to_thread(
+	ctx: Context<'_>,
+	press: &ComponentInteraction,
+	reply: &serenity::Message,
+	code: &str,
+	output: &str,
+) -> Result<(), Error> {
+	let can_create_threads = press.app_permissions.is_some_and(|permissions| {
+		permissions.create_public_threads() && permissions.send_messages_in_threads()
+	});
+
+	if can_create_threads {
+		let mut messages = vec![format!(
+			"Playground snippet shared by {} ({})",
+			ctx.author().mention(),
+			reply.link()
+		)];
+		messages.extend(crate::helpers::split_into_codeblocks(code, "rust"));
+		messages.push("Output:".to_owned());
+		messages.extend(crate::helpers::split_into_codeblocks(output, ""));
+
+		let thread_name = format!("Playground snippet by {}", ctx.author().name);
+		match crate::helpers::post_in_new_thread(ctx, reply, &thread_name, &messages).await {
+			Ok(_) => return Ok(()),
+			Err(e) => warn!("failed to share playground snippet to a thread: {}", e),
+		}
+	}
+
+	reply
+		.channel_id
+		.send_message(
+			ctx,
+			serenity::CreateMessage::new()
+				.content(format!(
+					"Couldn't start a thread here, so here's the code shared by {}",
+					ctx.author().mention()
+				))
+				.add_file(serenity::CreateAttachment::bytes(
+					code.as_bytes().to_vec(),
+					"playground.rs",
+				))
+				.reference_message(reply)
+				.allowed_mentions(serenity::CreateAllowedMentions::new()),
+		)
+		.await?;
+
+	Ok(())
+}
+
 // This function must not break when provided non-formatted text with messed up formatting: rustfmt
 // may not be installed on the host's computer!
 pub fn strip_fn_main_boilerplate_from_formatted(text: &str) -> String {
@@ -373,6 +725,16 @@ pub fn strip_fn_main_boilerplate_from_formatted(text: &str) -> String {
 /// If the program doesn't compile, the compiler output is returned. If it did compile and run,
 /// compiler output (i.e. warnings) is shown only when show_compiler_warnings is true.
 pub fn format_play_eval_stderr(stderr: &str, show_compiler_warnings: bool) -> String {
+	format_wrapped_play_eval_stderr(stderr, show_compiler_warnings, &[])
+}
+
+/// Like [`format_play_eval_stderr`], for code that may have been wrapped by [`maybe_wrapped`].
+/// Diagnostics that show one of the `wrapper_lines` (see [`wrapper_line_numbers`]) get a note.
+pub fn format_wrapped_play_eval_stderr(
+	stderr: &str,
+	show_compiler_warnings: bool,
+	wrapper_lines: &[usize],
+) -> String {
 	// Extract core compiler output and remove boilerplate lines from top and bottom
 	let compiler_output = extract_relevant_lines(
 		stderr,
@@ -387,13 +749,18 @@ pub fn format_play_eval_stderr(stderr: &str, show_compiler_warnings: bool) -> St
 			"Finished ",
 		],
 	);
+	let compiler_output: &str = &annotate_wrapper_diagnostics(compiler_output, wrapper_lines);
 
 	// If the program actually ran, compose compiler output and program stderr
 	// Using "Finished " here instead of "Running `target" because this method is also used by
 	// e.g. -Zunpretty=XXX family commands which don't actually run anything
 	if stderr.contains("Finished ") {
 		// Program successfully compiled, so compiler output will be just warnings
-		let program_stderr = extract_relevant_lines(stderr, &["Finished ", "Running `target"], &[]);
+		let program_stderr = extract_relevant_lines(
+			stderr,
+			&["Finished ", "Running `target", "Running unittests"],
+			&["Doc-tests"],
+		);
 
 		if show_compiler_warnings {
 			// Concatenate compiler output and program stderr with a newline
@@ -414,6 +781,152 @@ pub fn format_play_eval_stderr(stderr: &str, show_compiler_warnings: bool) -> St
 	}
 }
 
+/// Line numbers of the opening and closing lines of the `fn main` wrapper, if [`maybe_wrapped`]
+/// wrapped the code
+#[allow(clippy::ptr_arg)] // Borrowed code wasn't wrapped
+pub fn wrapper_line_numbers(code: &Cow<'_, str>) -> Vec<usize> {
+	let Cow::Owned(code) = code else {
+		return Vec::new();
+	};
+	// Only the hoisted crate attributes come before the opening line, and the closing line is last
+	let opening_line = code
+		.lines()
+		.position(|line| !line.starts_with("#!["))
+		.map(|index| index + 1);
+	opening_line
+		.into_iter()
+		.chain(Some(code.lines().count()))
+		.collect()
+}
+
+const WRAPPER_NOTE: &str =
+	"   = note: this comes from the implicit `fn main` wrapper, add your own `fn main` to avoid it";
+
+/// Points out diagnostics that show lines of the `fn main` wrapper added by [`maybe_wrapped`],
+/// which would otherwise be confusing
+fn annotate_wrapper_diagnostics(compiler_output: &str, wrapper_lines: &[usize]) -> String {
+	if wrapper_lines.is_empty() {
+		return compiler_output.to_owned();
+	}
+
+	let mut annotated = Vec::new();
+	// Whether the current diagnostic shows a wrapper line. The note goes at its end, so that it
+	// doesn't come between a source line and its markers.
+	let mut from_wrapper = false;
+	for line in compiler_output.lines() {
+		let ends_diagnostic =
+			line.trim().is_empty() || line.starts_with("error") || line.starts_with("warning");
+		if ends_diagnostic && from_wrapper {
+			annotated.push(WRAPPER_NOTE);
+			from_wrapper = false;
+		}
+		annotated.push(line);
+
+		// Source lines in diagnostics look like `12 | code`
+		let source_line_number = line
+			.split_once(" | ")
+			.and_then(|(line_number, _)| line_number.trim().parse::<usize>().ok());
+		from_wrapper |=
+			source_line_number.is_some_and(|line_number| wrapper_lines.contains(&line_number));
+	}
+	if from_wrapper {
+		annotated.push(WRAPPER_NOTE);
+	}
+	annotated.join("\n")
+}
+
+/// Condenses `cargo test` output into a checklist of test outcomes, followed by the summary line
+/// and the output of the failed tests only. Returns `None` if the output isn't from a test run.
+pub fn format_test_output(stdout: &str) -> Option<String> {
+	// Doc-tests get their own summary line afterwards, but the first one is for the unit tests
+	let summary = stdout
+		.lines()
+		.find(|line| line.starts_with("test result:"))?;
+
+	let mut output = String::new();
+	for line in stdout.lines() {
+		let Some((name, outcome)) = line
+			.strip_prefix("test ")
+			.and_then(|test| test.split_once(" ... "))
+		else {
+			continue;
+		};
+		let emoji = match outcome {
+			"ok" => "✅",
+			"FAILED" => "❌",
+			outcome if outcome.starts_with("ignored") => "⏭️",
+			_ => "❔",
+		};
+		output += &format!("{} {}\n", emoji, name);
+	}
+	output += summary;
+	output.push('\n');
+
+	// The output of failed tests is listed between two "failures:" headers
+	if let Some(failures) = stdout.split("\nfailures:\n").nth(1) {
+		output.push('\n');
+		output += failures.trim();
+		output.push('\n');
+	}
+
+	Some(output)
+}
+
+/// Lines of program output, ignoring trailing whitespace on each line and trailing empty lines,
+/// which are invisible in Discord anyway
+fn normalized_output_lines(output: &str) -> Vec<&str> {
+	let mut lines = output.lines().map(str::trim_end).collect::<Vec<_>>();
+	while lines.last() == Some(&"") {
+		lines.pop();
+	}
+	lines
+}
+
+/// Compares the actual output of a program with the expected output. Returns a verdict line,
+/// followed by a line diff if they differ: `-` lines were expected but missing, `+` lines were
+/// printed but not expected.
+pub fn format_output_diff(expected: &str, actual: &str) -> String {
+	// Bounds the quadratic diff below; Discord can't show more than this anyway
+	const MAX_DIFFED_LINES: usize = 500;
+
+	let expected = normalized_output_lines(expected);
+	let actual = normalized_output_lines(actual);
+	if expected == actual {
+		return format!("✅ Output matches\n\n{}\n", actual.join("\n"));
+	}
+	let expected = &expected[..expected.len().min(MAX_DIFFED_LINES)];
+	let actual = &actual[..actual.len().min(MAX_DIFFED_LINES)];
+
+	// Longest common subsequence lengths of all suffix pairs
+	let mut lcs = vec![vec![0_usize; actual.len() + 1]; expected.len() + 1];
+	for i in (0..expected.len()).rev() {
+		for j in (0..actual.len()).rev() {
+			lcs[i][j] = if expected[i] == actual[j] {
+				lcs[i + 1][j + 1] + 1
+			} else {
+				lcs[i + 1][j].max(lcs[i][j + 1])
+			};
+		}
+	}
+
+	let mut output = String::from("❌ Output differs (- expected, + actual)\n\n");
+	let (mut i, mut j) = (0, 0);
+	while i < expected.len() || j < actual.len() {
+		if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+			output += &format!("  {}\n", expected[i]);
+			i += 1;
+			j += 1;
+		} else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+			output += &format!("+ {}\n", actual[j]);
+			j += 1;
+		} else {
+			output += &format!("- {}\n", expected[i]);
+			i += 1;
+		}
+	}
+	output
+}
+
 pub fn stub_message(ctx: Context) -> String {
 	let mut stub_message = String::from("_Running code on playground..._\n");
 
@@ -430,3 +943,106 @@ pub fn stub_message(ctx: Context) -> String {
 	stub_message.truncate(2000);
 	stub_message
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MISMATCHED_TYPES: &str = "error[E0308]: mismatched types
+ --> src/main.rs:2:14
+  |
+2 | let x: i32 = \"a\";
+  |        ---   ^^^ expected `i32`, found `&str`
+  |        |
+  |        expected due to this
+";
+
+	const QUESTION_MARK_IN_MAIN: &str = "error[E0277]: the `?` operator can only be used in a function that returns `Result` or `Option`
+ --> src/main.rs:2:17
+  |
+1 | fn main() {
+  | --------- this function should return `Result` or `Option` to accept `?`
+2 |     \"1\".parse::<i32>()?;
+  |                     ^ cannot use the `?` operator in a function that returns `()`
+";
+
+	#[test]
+	fn user_written_main_isnt_the_wrapper() {
+		let code = maybe_wrap(
+			"fn main() {\n    \"1\".parse::<i32>()?;\n}",
+			ResultHandling::None,
+		);
+		let wrapper_lines = wrapper_line_numbers(&code);
+		assert!(wrapper_lines.is_empty());
+		assert_eq!(
+			annotate_wrapper_diagnostics(QUESTION_MARK_IN_MAIN, &wrapper_lines),
+			QUESTION_MARK_IN_MAIN
+		);
+	}
+
+	#[test]
+	fn wrapper_line_numbers_of_wrapped_code() {
+		let code = maybe_wrap("\"1\".parse::<i32>()?;", ResultHandling::None);
+		assert_eq!(wrapper_line_numbers(&code), [1, 3]);
+
+		// Crate attributes are hoisted above the wrapper
+		let code = maybe_wrap("#![allow(unused)]\nlet x = 1;", ResultHandling::Discard);
+		assert_eq!(wrapper_line_numbers(&code), [2, 4]);
+	}
+
+	#[test]
+	fn annotates_diagnostics_showing_the_wrapper() {
+		let code = maybe_wrap("\"1\".parse::<i32>()?;", ResultHandling::None);
+		let annotated = annotate_wrapper_diagnostics(
+			&format!("{}\n{}", QUESTION_MARK_IN_MAIN, MISMATCHED_TYPES),
+			&wrapper_line_numbers(&code),
+		);
+		assert_eq!(annotated.matches(WRAPPER_NOTE).count(), 1);
+		// The note ends the diagnostic that shows the wrapper
+		assert!(annotated.contains(&format!("returns `()`\n{}\n\nerror[E0308]", WRAPPER_NOTE)));
+	}
+
+	#[test]
+	fn matching_output() {
+		assert_eq!(
+			format_output_diff("1\n2\n", "1\n2\n"),
+			"✅ Output matches\n\n1\n2\n"
+		);
+	}
+
+	#[test]
+	fn output_diff_ignores_trailing_whitespace_and_newlines() {
+		assert_eq!(
+			format_output_diff("1\n2", "1  \n2\t\n\n\n"),
+			"✅ Output matches\n\n1\n2\n"
+		);
+		assert_eq!(
+			format_output_diff("1\r\n2\r\n", "1\n2"),
+			"✅ Output matches\n\n1\n2\n"
+		);
+	}
+
+	#[test]
+	fn output_diff_of_an_extra_line() {
+		assert_eq!(
+			format_output_diff("1\n3", "1\n2\n3"),
+			"❌ Output differs (- expected, + actual)\n\n  1\n+ 2\n  3\n"
+		);
+	}
+
+	#[test]
+	fn output_diff_of_a_missing_line() {
+		assert_eq!(
+			format_output_diff("1\n2\n3", "1\n3"),
+			"❌ Output differs (- expected, + actual)\n\n  1\n- 2\n  3\n"
+		);
+	}
+
+	#[test]
+	fn output_diff_of_a_changed_line() {
+		assert_eq!(
+			format_output_diff("1\n2", "1\n4"),
+			"❌ Output differs (- expected, + actual)\n\n  1\n+ 4\n- 2\n"
+		);
+	}
+}
@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+use poise::serenity_prelude as serenity;
+use tracing::{info, warn};
+
+use crate::types::{Context, Data};
+
+/// The release manifests only change once a day, with the nightly
+const CACHE_DURATION: Duration = Duration::from_secs(60 * 60 * 24);
+
+const CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+#[derive(Debug, Clone)]
+pub struct ToolchainRelease {
+	channel: &'static str,
+	/// Version of the `rust` package, e.g. `1.78.0 (9b00956e5 2024-04-29)`
+	version: String,
+	/// Day the toolchain was released on this channel
+	date: chrono::NaiveDate,
+}
+
+#[derive(Debug, Default)]
+pub struct ToolchainReleases {
+	releases: Vec<ToolchainRelease>,
+	last_update_time: Option<Instant>,
+}
+
+/// Extracts the release date and the version of the `rust` package from a channel manifest. The
+/// manifest is TOML, but only these two values are needed, so it's scanned line by line.
+fn parse_channel_manifest(
+	channel: &'static str,
+	manifest: &str,
+) -> Result<ToolchainRelease, Error> {
+	let string_value = |line: &str, key: &str| {
+		line.strip_prefix(key)?
+			.trim_start()
+			.strip_prefix('=')?
+			.trim()
+			.strip_prefix('"')?
+			.strip_suffix('"')
+			.map(str::to_owned)
+	};
+
+	let date = manifest
+		.lines()
+		.find_map(|line| string_value(line, "date"))
+		.ok_or(anyhow!("no date in the {} manifest", channel))?;
+	let version = manifest
+		.lines()
+		.skip_while(|line| line.trim() != "[pkg.rust]")
+		.find_map(|line| string_value(line, "version"))
+		.ok_or(anyhow!("no rust version in the {} manifest", channel))?;
+
+	Ok(ToolchainRelease {
+		channel,
+		version,
+		date: date.parse()?,
+	})
+}
+
+async fn fetch_toolchain_release(
+	http: &reqwest::Client,
+	channel: &'static str,
+) -> Result<ToolchainRelease, Error> {
+	let manifest = http
+		.get(format!(
+			"https://static.rust-lang.org/dist/channel-rust-{}.toml",
+			channel
+		))
+		.send()
+		.await?
+		.error_for_status()?
+		.text()
+		.await?;
+	parse_channel_manifest(channel, &manifest)
+}
+
+/// Returns the current releases of all channels, fetched at most once per [`CACHE_DURATION`].
+/// If fetching fails, the previous releases are returned as long as there are any, and flagged as
+/// outdated.
+async fn toolchain_releases(data: &Data) -> Result<(Vec<ToolchainRelease>, bool), Error> {
+	let (cached, is_fresh) = {
+		let cache = data.toolchain_releases.lock().unwrap();
+		let is_fresh = cache
+			.last_update_time
+			.is_some_and(|last_update_time| last_update_time.elapsed() < CACHE_DURATION);
+		(cache.releases.clone(), is_fresh)
+	};
+	if is_fresh {
+		return Ok((cached, false));
+	}
+
+	info!("fetching toolchain release manifests");
+	let fetched = futures::future::try_join_all(
+		CHANNELS
+			.iter()
+			.map(|channel| fetch_toolchain_release(&data.http, channel)),
+	)
+	.await;
+	match fetched {
+		Ok(releases) => {
+			*data.toolchain_releases.lock().unwrap() = ToolchainReleases {
+				releases: releases.clone(),
+				last_update_time: Some(Instant::now()),
+			};
+			Ok((releases, false))
+		}
+		Err(e) if !cached.is_empty() => {
+			warn!(
+				"failed to fetch toolchain releases, using cached ones: {:?}",
+				e
+			);
+			Ok((cached, true))
+		}
+		Err(e) => {
+			Err(e.context("Couldn't fetch the current Rust releases, please try again later"))
+		}
+	}
+}
+
+/// Shows the current stable, beta and nightly Rust versions
+///
+/// /rustup
+///
+/// Shows the version of the Rust toolchain on each release channel, and when it was released. \
+/// The versions are read from the release manifests on static.rust-lang.org.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	aliases("toolchains"),
+	category = "Utilities"
+)]
+pub async fn rustup(ctx: Context<'_>) -> Result<(), Error> {
+	ctx.defer().await?;
+	let (releases, outdated) = toolchain_releases(ctx.data()).await?;
+
+	let description = releases
+		.iter()
+		.map(|release| {
+			let released_at = release
+				.date
+				.and_hms_opt(0, 0, 0)
+				.unwrap_or_default()
+				.and_utc()
+				.timestamp();
+			format!(
+				"**{}**: `{}`, released <t:{}:D>\n",
+				release.channel, release.version, released_at
+			)
+		})
+		.collect::<String>();
+
+	let mut embed = serenity::CreateEmbed::new()
+		.color(crate::types::EMBED_COLOR)
+		.title("Current Rust releases")
+		.description(description)
+		.footer(serenity::CreateEmbedFooter::new(
+			"Install or update with: rustup update",
+		));
+	if outdated {
+		embed = embed.footer(serenity::CreateEmbedFooter::new(
+			"Couldn't fetch the latest releases, these may be outdated",
+		));
+	}
+
+	ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+	Ok(())
+}
@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Error};
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use tracing::{info, warn};
+
+use crate::types::{Context, Data};
+
+const SERVER_ICONS_DIR: &str = "assets/server-icons";
+
+/// Bounds for the configured rotation interval, in hours. Discord rate limits edits to the server,
+/// and an icon that stays for more than a month isn't much of a rotation anymore.
+const MIN_INTERVAL_HOURS: u64 = 1;
+const MAX_INTERVAL_HOURS: u64 = 30 * 24;
+
+/// How long after any rotation `?roticon` can be used again
+const MANUAL_ROTATION_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+/// Range of hours to wait between two automatic rotations, configured like `24-48`
+#[derive(Debug, Clone, Copy)]
+pub struct RotationInterval {
+	min_hours: u64,
+	max_hours: u64,
+}
+
+impl std::str::FromStr for RotationInterval {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (min, max) = s.split_once('-').unwrap_or((s, s));
+		let interval = Self {
+			min_hours: min.trim().parse()?,
+			max_hours: max.trim().parse()?,
+		};
+		if interval.min_hours < MIN_INTERVAL_HOURS
+			|| interval.max_hours > MAX_INTERVAL_HOURS
+			|| interval.min_hours > interval.max_hours
+		{
+			bail!(
+				"the server icon rotation interval must be a range of hours like `24-48` within {} \
+				and {} hours, got `{}`",
+				MIN_INTERVAL_HOURS,
+				MAX_INTERVAL_HOURS,
+				s
+			);
+		}
+		Ok(interval)
+	}
+}
+
+impl RotationInterval {
+	fn random_duration(self) -> Duration {
+		Duration::from_secs(
+			rand::thread_rng().gen_range(self.min_hours * 60 * 60..=self.max_hours * 60 * 60),
+		)
+	}
+}
+
+#[derive(Debug, Default)]
+struct ServerIconState {
+	/// File name of the current icon, once known
+	current: Option<String>,
+	last_rotation: Option<Instant>,
+}
+
+/// Shared between the automatic rotation and `?roticon`
+#[derive(Debug, Default)]
+pub struct ServerIcons {
+	/// The icon is only rotated automatically if an interval is configured
+	interval: Option<RotationInterval>,
+	state: Mutex<ServerIconState>,
+	/// Notified on manual rotations, so that the wait for the next automatic one starts over
+	rotated_manually: tokio::sync::Notify,
+}
+
+impl ServerIcons {
+	pub fn new(interval: Option<RotationInterval>) -> Self {
+		Self {
+			interval,
+			..Default::default()
+		}
+	}
+}
+
+fn icon_paths() -> Result<Vec<PathBuf>, Error> {
+	Ok(std::fs::read_dir(SERVER_ICONS_DIR)?
+		.filter_map(|entry| Some(entry.ok()?.path()))
+		.filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+		.collect())
+}
+
+/// Picks a random icon, other than the current one unless it's the only one
+fn pick_icon(paths: &[PathBuf], current: Option<&str>) -> Option<PathBuf> {
+	let others = paths
+		.iter()
+		.filter(|path| path.file_name().and_then(|name| name.to_str()) != current)
+		.collect::<Vec<_>>();
+	others
+		.choose(&mut rand::thread_rng())
+		.map(|path| (*path).clone())
+		.or_else(|| paths.first().cloned())
+}
+
+async fn rotate_server_icon(
+	http: &serenity::Http,
+	database: &sqlx::PgPool,
+	guild_id: serenity::GuildId,
+	icons: &ServerIcons,
+) -> Result<PathBuf, Error> {
+	let mut current = icons.state.lock().unwrap().current.clone();
+	if current.is_none() {
+		// The icon set before the bot restarted
+		current = crate::database::server_icons::get_current(database, guild_id).await?;
+	}
+	let icon = pick_icon(&icon_paths()?, current.as_deref())
+		.ok_or(anyhow!("no server icons found in {}", SERVER_ICONS_DIR))?;
+	let icon_name = icon
+		.file_name()
+		.unwrap_or_default()
+		.to_string_lossy()
+		.into_owned();
+
+	let attachment = serenity::CreateAttachment::path(&icon).await?;
+	guild_id
+		.edit(http, serenity::EditGuild::new().icon(Some(&attachment)))
+		.await?;
+	info!("Changed the server icon to {}", icon_name);
+
+	*icons.state.lock().unwrap() = ServerIconState {
+		current: Some(icon_name.clone()),
+		last_rotation: Some(Instant::now()),
+	};
+	if let Err(e) = crate::database::server_icons::set_current(database, guild_id, &icon_name).await
+	{
+		warn!("Couldn't save the current server icon: {:?}", e);
+	}
+	Ok(icon)
+}
+
+/// Changes the server icon to a random one every once in a while, see [`RotationInterval`]. Does
+/// nothing unless `SERVER_ICON_INTERVAL_HOURS` is configured.
+pub fn spawn_server_icon_changer(ctx: &serenity::Context, data: &Data) {
+	let Some(interval) = data.server_icons.interval else {
+		info!("SERVER_ICON_INTERVAL_HOURS isn't set, not rotating the server icon");
+		return;
+	};
+	let http = ctx.http.clone();
+	let database = data.database.clone();
+	let guild_id = data.discord_guild_id;
+	let icons = data.server_icons.clone();
+
+	tokio::spawn(async move {
+		loop {
+			// Manual rotations restart the wait
+			while tokio::time::timeout(
+				interval.random_duration(),
+				icons.rotated_manually.notified(),
+			)
+			.await
+			.is_ok()
+			{}
+
+			if let Err(e) = rotate_server_icon(&http, &database, guild_id, &icons).await {
+				warn!("Couldn't change the server icon: {:?}", e);
+			}
+		}
+	});
+}
+
+/// Change the server icon now
+///
+/// /roticon
+///
+/// Changes the server icon to a random one other than the current, like the automatic rotation \
+/// does. If the icon is rotated automatically, the wait for the next rotation starts over.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	category = "Utilities"
+)]
+pub async fn roticon(ctx: Context<'_>) -> Result<(), Error> {
+	let icons = &ctx.data().server_icons;
+	let last_rotation = icons.state.lock().unwrap().last_rotation;
+	if let Some(last_rotation) = last_rotation {
+		let since_last_rotation = last_rotation.elapsed();
+		if since_last_rotation < MANUAL_ROTATION_COOLDOWN {
+			bail!(
+				"The server icon was changed recently, please try again in {} minutes",
+				(MANUAL_ROTATION_COOLDOWN - since_last_rotation).as_secs() / 60 + 1
+			);
+		}
+	}
+
+	ctx.defer().await?;
+	let icon = rotate_server_icon(
+		ctx.http(),
+		&ctx.data().database,
+		ctx.data().discord_guild_id,
+		icons,
+	)
+	.await?;
+	icons.rotated_manually.notify_one();
+
+	ctx.say(format!(
+		"Changed the server icon to `{}`",
+		icon.file_name().unwrap_or_default().to_string_lossy()
+	))
+	.await?;
+
+	Ok(())
+}
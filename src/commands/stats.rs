@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Error};
+use chrono::Timelike;
+use futures::StreamExt;
+use poise::serenity_prelude as serenity;
+use poise::serenity_prelude::Mentionable;
+
+use crate::types::Context;
+
+const DEFAULT_WINDOW_HOURS: u32 = 24;
+const MAX_WINDOW_HOURS: u32 = 7 * 24;
+
+/// Most messages scanned for one summary. Messages are fetched 100 at a time, so this bounds both
+/// the number of requests and how long the command takes.
+const MAX_SCANNED_MESSAGES: usize = 2000;
+
+const TOP_PARTICIPANTS: usize = 5;
+const TOP_HOURS: usize = 3;
+
+/// Activity statistics for moderators
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	hide_in_help,
+	subcommands("channel"),
+	subcommand_required,
+	category = "Utilities",
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn stats(_: Context<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Summarize recent activity in a channel
+///
+/// /stats channel [channel] [hours]
+///
+/// Counts the messages in the channel (this one by default) over the last hours (24 by default, \
+/// at most a week), and shows who posted the most and at what times of day. At most the latest \
+/// 2000 messages are looked at, so busy channels are only summarized partially.
+#[poise::command(prefix_command, slash_command, guild_only)]
+pub async fn channel(
+	ctx: Context<'_>,
+	#[description = "Channel to summarize (default: this one)"] channel: Option<
+		serenity::GuildChannel,
+	>,
+	#[description = "How many hours to look back (default 24)"] hours: Option<u32>,
+) -> Result<(), Error> {
+	let channel_id = match channel {
+		Some(channel) => {
+			let member = ctx
+				.author_member()
+				.await
+				.ok_or(anyhow!("Failed to fetch server member."))?;
+			if Some(channel.guild_id) != ctx.guild_id()
+				|| !crate::commands::mentions::can_view_channel(
+					ctx.serenity_context(),
+					&channel,
+					&member,
+				) {
+				bail!("You can't view {}", channel.mention());
+			}
+			channel.id
+		}
+		None => ctx.channel_id(),
+	};
+	let hours = hours.unwrap_or(DEFAULT_WINDOW_HOURS);
+	if hours == 0 || hours > MAX_WINDOW_HOURS {
+		bail!(
+			"The window must be between 1 and {} hours",
+			MAX_WINDOW_HOURS
+		);
+	}
+	let since = chrono::Utc::now() - chrono::Duration::hours(hours.into());
+
+	// The summary may be about a channel that not everyone here can see
+	ctx.defer_ephemeral().await?;
+
+	let mut total_messages = 0;
+	let mut messages_per_author = HashMap::<serenity::UserId, usize>::new();
+	let mut messages_per_hour = [0_usize; 24];
+	let mut oldest_scanned = None;
+	let mut scan_capped = false;
+	let mut messages = std::pin::pin!(channel_id.messages_iter(ctx));
+	while let Some(message) = messages.next().await {
+		let message = message?;
+		// Messages come newest first
+		if *message.timestamp < since {
+			break;
+		}
+		if total_messages == MAX_SCANNED_MESSAGES {
+			scan_capped = true;
+			break;
+		}
+
+		total_messages += 1;
+		oldest_scanned = Some(message.timestamp);
+		if !message.author.bot {
+			*messages_per_author.entry(message.author.id).or_default() += 1;
+		}
+		messages_per_hour[message.timestamp.hour() as usize] += 1;
+	}
+
+	let mut top_participants = messages_per_author.iter().collect::<Vec<_>>();
+	top_participants.sort_by(|a, b| b.1.cmp(a.1));
+	let top_participants = top_participants
+		.iter()
+		.take(TOP_PARTICIPANTS)
+		.map(|(user_id, count)| format!("<@{}>: {}\n", user_id, count))
+		.collect::<String>();
+
+	let mut peak_hours = messages_per_hour
+		.iter()
+		.enumerate()
+		.filter(|(_, count)| **count > 0)
+		.collect::<Vec<_>>();
+	peak_hours.sort_by(|a, b| b.1.cmp(a.1));
+	let peak_hours = peak_hours
+		.iter()
+		.take(TOP_HOURS)
+		.map(|(hour, count)| format!("{:02}:00–{:02}:00: {}\n", hour, (hour + 1) % 24, count))
+		.collect::<String>();
+
+	let mut embed = serenity::CreateEmbed::new()
+		.color(crate::types::EMBED_COLOR)
+		.title(format!("Activity over the last {} hours", hours))
+		.description(format!("In <#{}>", channel_id))
+		.field("Messages", total_messages.to_string(), true)
+		.field("Participants", messages_per_author.len().to_string(), true);
+	if !top_participants.is_empty() {
+		embed = embed.field("Most messages", top_participants, false);
+	}
+	if !peak_hours.is_empty() {
+		embed = embed.field("Busiest hours (UTC)", peak_hours, false);
+	}
+	if let (true, Some(oldest_scanned)) = (scan_capped, oldest_scanned) {
+		embed = embed.field(
+			"Partial summary",
+			format!(
+				"Only the latest {} messages were counted, going back to <t:{}:f>",
+				MAX_SCANNED_MESSAGES,
+				oldest_scanned.unix_timestamp()
+			),
+			false,
+		);
+	}
+
+	ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+		.await?;
+
+	Ok(())
+}
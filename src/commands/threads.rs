@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use anyhow::{bail, Error};
+use poise::serenity_prelude as serenity;
+use tracing::info;
+
+use crate::helpers::MAX_THREAD_NAME_LENGTH;
+use crate::types::{Context, Data};
+
+const SOLVED_PREFIX: &str = "[SOLVED] ";
+const SOLVED_REACTION: char = '✅';
+
+/// Subscribers get at most one DM per thread within this time, however busy the thread is
+const SUBSCRIPTION_NOTIFICATION_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+const DEFAULT_SUBSCRIPTION_HOURS: u32 = 24;
+const MAX_SUBSCRIPTION_HOURS: u32 = 7 * 24;
+
+/// Longest part of the new message that's quoted in the DM
+const MAX_QUOTE_LENGTH: usize = 300;
+
+/// Returns whether the given channel is a thread that was created by the given user
+pub fn is_thread_owner(channel: &serenity::GuildChannel, user_id: serenity::UserId) -> bool {
+	channel.thread_metadata.is_some() && channel.owner_id == Some(user_id)
+}
+
+/// Prefixes the thread name with [`SOLVED_PREFIX`], unless it's already there. Returns `None`
+/// if the name doesn't need changing.
+pub fn solved_thread_name(name: &str) -> Option<String> {
+	if name.starts_with(SOLVED_PREFIX.trim_end()) {
+		return None;
+	}
+
+	Some(
+		format!("{}{}", SOLVED_PREFIX, name)
+			.chars()
+			.take(MAX_THREAD_NAME_LENGTH)
+			.collect(),
+	)
+}
+
+/// When the owner of a thread reacts to a message in it with ✅, that message is considered the
+/// accepted answer: it gets pinned and the thread is marked as solved.
+pub async fn handle_solved_reaction(
+	ctx: &serenity::Context,
+	reaction: &serenity::Reaction,
+) -> Result<(), Error> {
+	if reaction.emoji != serenity::ReactionType::from(SOLVED_REACTION) {
+		return Ok(());
+	}
+	let Some(user_id) = reaction.user_id else {
+		return Ok(());
+	};
+
+	let Some(mut thread) = reaction.channel(ctx).await?.guild() else {
+		return Ok(());
+	};
+	if !is_thread_owner(&thread, user_id) {
+		return Ok(());
+	}
+
+	info!(
+		"Marking thread {} as solved, accepted answer: {}",
+		thread.id, reaction.message_id
+	);
+
+	thread.pin(ctx, reaction.message_id).await?;
+
+	if let Some(name) = solved_thread_name(&thread.name) {
+		thread
+			.edit_thread(ctx, serenity::EditThread::new().name(name))
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// DMs the subscribers of a thread when a new message lands in it
+pub async fn handle_subscribed_thread_message(
+	ctx: &serenity::Context,
+	data: &Data,
+	message: &serenity::Message,
+) -> Result<(), Error> {
+	if message.author.bot
+		|| !data
+			.subscribed_threads
+			.read()
+			.unwrap()
+			.contains(&message.channel_id)
+	{
+		return Ok(());
+	}
+
+	let subscribers = crate::database::thread_subscriptions::take_due_subscribers(
+		&data.database,
+		message.channel_id,
+		message.author.id,
+		SUBSCRIPTION_NOTIFICATION_COOLDOWN,
+	)
+	.await?;
+	if subscribers.is_empty() {
+		// All subscriptions to this thread might have expired
+		return data.refresh_subscribed_threads().await;
+	}
+
+	let thread_name = message
+		.channel_id
+		.name(ctx)
+		.await
+		.unwrap_or_else(|_| "a thread".to_owned());
+	let quote = crate::helpers::quote_message(ctx, message, MAX_QUOTE_LENGTH);
+	for user_id in subscribers {
+		let user_id = serenity::UserId::new(user_id as u64);
+		info!(
+			"Notifying {} about new message {} in subscribed thread",
+			user_id, message.id
+		);
+		let embed = serenity::CreateEmbed::new()
+			.color(crate::types::EMBED_COLOR)
+			.title(format!("New reply in {}", thread_name))
+			.description(format!(
+				"**{}**: {}\n\n[Jump to the message]({})",
+				message.author.name,
+				quote,
+				message.link()
+			))
+			.footer(serenity::CreateEmbedFooter::new(
+				"Use /unremind-thread in the thread to stop these",
+			))
+			.timestamp(message.timestamp);
+		crate::helpers::send_notification_dm(ctx, user_id, embed).await;
+	}
+
+	Ok(())
+}
+
+/// Get a DM when someone replies in this thread
+///
+/// /remind-thread [hours]
+///
+/// Subscribes you to the current thread: whenever someone posts in it within the next hours \
+/// (24 by default, at most a week), you get a DM. DMs are sent at most once an hour per thread.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	guild_only,
+	rename = "remind-thread",
+	aliases("subscribe"),
+	category = "Utilities",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn remind_thread(
+	ctx: Context<'_>,
+	#[description = "For how many hours to get DMs (default 24)"] hours: Option<u32>,
+) -> Result<(), Error> {
+	let hours = hours.unwrap_or(DEFAULT_SUBSCRIPTION_HOURS);
+	if hours == 0 || hours > MAX_SUBSCRIPTION_HOURS {
+		bail!(
+			"You can subscribe for between 1 and {} hours",
+			MAX_SUBSCRIPTION_HOURS
+		);
+	}
+
+	let is_thread = ctx
+		.guild_channel()
+		.await
+		.is_some_and(|channel| channel.thread_metadata.is_some());
+	if !is_thread {
+		bail!("This command can only be used in threads");
+	}
+
+	let expires_at = chrono::Utc::now() + chrono::Duration::hours(hours.into());
+	crate::database::thread_subscriptions::subscribe(
+		&ctx.data().database,
+		ctx.channel_id(),
+		ctx.author().id,
+		expires_at,
+	)
+	.await?;
+	ctx.data().refresh_subscribed_threads().await?;
+
+	ctx.say(format!(
+		"You'll get a DM when someone posts here until <t:{}:f>.",
+		expires_at.timestamp()
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Stop getting DMs about replies in this thread
+///
+/// /unremind-thread
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	guild_only,
+	rename = "unremind-thread",
+	aliases("unsubscribe"),
+	category = "Utilities",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn unremind_thread(ctx: Context<'_>) -> Result<(), Error> {
+	let was_subscribed = crate::database::thread_subscriptions::unsubscribe(
+		&ctx.data().database,
+		ctx.channel_id(),
+		ctx.author().id,
+	)
+	.await?;
+	ctx.data().refresh_subscribed_threads().await?;
+
+	if was_subscribed {
+		ctx.say("You won't get DMs about this thread anymore.")
+			.await?;
+	} else {
+		ctx.say("You weren't subscribed to this thread.").await?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+use poise::serenity_prelude as serenity;
+use tracing::{info, warn};
+
+use crate::types::{Context, Data};
+
+const FEED_URL: &str = "https://this-week-in-rust.org/rss.xml";
+
+/// New issues come out weekly, so there's no need to fetch the feed more often
+const CACHE_DURATION: Duration = Duration::from_secs(60 * 60 * 3);
+
+/// Length of the summary of the issue shown in the embed
+const MAX_SUMMARY_LENGTH: usize = 300;
+
+#[derive(Debug, Clone)]
+pub struct TwirIssue {
+	title: String,
+	link: String,
+	summary: String,
+	published: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Debug, Default)]
+pub struct TwirCache {
+	issue: Option<TwirIssue>,
+	last_update_time: Option<Instant>,
+}
+
+/// Turns the HTML of a feed item into plain text: tags are dropped, common entities decoded and
+/// whitespace collapsed
+fn html_to_text(html: &str) -> String {
+	let mut text = String::with_capacity(html.len());
+	let mut in_tag = false;
+	for c in html.chars() {
+		match c {
+			'<' => in_tag = true,
+			'>' if in_tag => {
+				in_tag = false;
+				text.push(' ');
+			}
+			_ if !in_tag => text.push(c),
+			_ => {}
+		}
+	}
+
+	text.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&amp;", "&")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Cuts the text after at most `max_length` characters, at a word boundary
+fn shorten(text: &str, max_length: usize) -> String {
+	if text.chars().count() <= max_length {
+		return text.to_owned();
+	}
+
+	let cut = text.chars().take(max_length).collect::<String>();
+	match cut.rsplit_once(' ') {
+		Some((shortened, _)) => format!("{}…", shortened),
+		None => format!("{}…", cut),
+	}
+}
+
+async fn fetch_latest_issue(http: &reqwest::Client) -> Result<TwirIssue, Error> {
+	let feed = http
+		.get(FEED_URL)
+		.send()
+		.await?
+		.error_for_status()?
+		.bytes()
+		.await?;
+	let channel = rss::Channel::read_from(&feed[..])?;
+
+	// Items are ordered newest first
+	let item = channel
+		.items()
+		.first()
+		.ok_or(anyhow!("the This Week in Rust feed has no issues"))?;
+	Ok(TwirIssue {
+		title: item.title().unwrap_or("This Week in Rust").to_owned(),
+		link: item
+			.link()
+			.unwrap_or("https://this-week-in-rust.org")
+			.to_owned(),
+		summary: shorten(
+			&html_to_text(item.description().unwrap_or_default()),
+			MAX_SUMMARY_LENGTH,
+		),
+		published: item
+			.pub_date()
+			.and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok()),
+	})
+}
+
+/// Returns the latest issue, fetched at most once per [`CACHE_DURATION`]. If fetching fails, the
+/// previously fetched issue is returned if there is one.
+async fn latest_issue(data: &Data) -> Result<TwirIssue, Error> {
+	let (cached, is_fresh) = {
+		let cache = data.twir_cache.lock().unwrap();
+		let is_fresh = cache
+			.last_update_time
+			.is_some_and(|last_update_time| last_update_time.elapsed() < CACHE_DURATION);
+		(cache.issue.clone(), is_fresh)
+	};
+	if let Some(issue) = cached.clone().filter(|_| is_fresh) {
+		return Ok(issue);
+	}
+
+	info!("fetching the This Week in Rust feed");
+	match fetch_latest_issue(&data.http).await {
+		Ok(issue) => {
+			*data.twir_cache.lock().unwrap() = TwirCache {
+				issue: Some(issue.clone()),
+				last_update_time: Some(Instant::now()),
+			};
+			Ok(issue)
+		}
+		Err(e) => {
+			warn!("failed to fetch the This Week in Rust feed: {:?}", e);
+			cached.ok_or(anyhow!(
+				"Couldn't fetch This Week in Rust, please try again later"
+			))
+		}
+	}
+}
+
+/// Shows the latest issue of This Week in Rust
+///
+/// /thisweekinrust
+///
+/// Links the latest issue of This Week in Rust, the weekly newsletter about what's going on in \
+/// the Rust community, with a short summary.
+#[poise::command(prefix_command, slash_command, aliases("twir"), category = "Utilities")]
+pub async fn thisweekinrust(ctx: Context<'_>) -> Result<(), Error> {
+	ctx.defer().await?;
+	let issue = latest_issue(ctx.data()).await?;
+
+	let mut embed = serenity::CreateEmbed::new()
+		.color(crate::types::EMBED_COLOR)
+		.title(issue.title)
+		.url(issue.link)
+		.description(issue.summary)
+		.footer(serenity::CreateEmbedFooter::new("This Week in Rust"));
+	if let Some(published) = issue.published {
+		embed = embed.timestamp(serenity::Timestamp::from_unix_timestamp(
+			published.timestamp(),
+		)?);
+	}
+
+	ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+	Ok(())
+}
@@ -1,8 +1,12 @@
-use anyhow::{anyhow, Error};
+use std::sync::atomic::Ordering;
+
+use anyhow::{anyhow, bail, Error};
 use poise::serenity_prelude as serenity;
+use poise::serenity_prelude::Mentionable;
 use poise::serenity_prelude::Timestamp;
 
-use crate::types::Context;
+use crate::helpers::CommandHelp;
+use crate::types::{Context, Data};
 
 /// Evaluates Go code
 #[poise::command(
@@ -34,17 +38,217 @@ pub async fn source(ctx: Context<'_>) -> Result<(), Error> {
 	Ok(())
 }
 
+/// GitHub repository that `?feedback` files issues on
+const BOT_REPOSITORY: &str = "rust-community-discord/ferrisbot-for-discord";
+
+/// Limits for the issue title and the reporter's description, well below GitHub's own limits
+const MAX_ISSUE_TITLE_LENGTH: usize = 80;
+const MAX_FEEDBACK_LENGTH: usize = 4000;
+
+/// Escapes text so it can't @-mention GitHub users or teams, or close and reference issues with
+/// keywords when quoted in an issue
+fn sanitize_for_github(text: &str) -> String {
+	text.replace('@', "@\u{200B}").replace('#', "#\u{200B}")
+}
+
+/// File a bug report or feedback about the bot
+///
+/// /feedback <description>
+///
+/// Opens an issue on the bot's GitHub repository. The first line of the description becomes the \
+/// issue title. The issue mentions who reported it and where.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	guild_only,
+	user_cooldown = 600,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn feedback(
+	ctx: Context<'_>,
+	#[description = "What's wrong or could be better? The first line is used as the title"]
+	#[rest]
+	description: String,
+) -> Result<(), Error> {
+	let Some(github_token) = &ctx.data().github_token else {
+		bail!("Filing issues isn't configured for this bot");
+	};
+	let description = description.trim();
+	if description.is_empty() {
+		bail!("Please describe the problem");
+	}
+
+	let title = description
+		.lines()
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.take(MAX_ISSUE_TITLE_LENGTH)
+		.collect::<String>();
+	let description = description
+		.chars()
+		.take(MAX_FEEDBACK_LENGTH)
+		.collect::<String>();
+	let channel_name = ctx
+		.channel_id()
+		.name(ctx)
+		.await
+		.unwrap_or_else(|_| "<unknown>".to_owned());
+	let body = format!(
+		"{}\n\n---\nReported on Discord by `{}` in `#{}` using `{}{}`.",
+		sanitize_for_github(&description),
+		sanitize_for_github(&ctx.author().name),
+		sanitize_for_github(&channel_name),
+		ctx.prefix(),
+		ctx.invoked_command_name(),
+	);
+
+	#[derive(serde::Deserialize)]
+	struct IssueResponse {
+		html_url: String,
+	}
+
+	let issue = ctx
+		.data()
+		.http
+		.post(format!(
+			"https://api.github.com/repos/{}/issues",
+			BOT_REPOSITORY
+		))
+		.bearer_auth(github_token)
+		.header(reqwest::header::USER_AGENT, "ferrisbot-for-discord")
+		.header(reqwest::header::ACCEPT, "application/vnd.github+json")
+		.json(&serde_json::json!({
+			"title": sanitize_for_github(&title),
+			"body": body,
+		}))
+		.send()
+		.await?
+		.error_for_status()
+		.map_err(|e| anyhow!("GitHub didn't accept the issue: {}", e))?
+		.json::<IssueResponse>()
+		.await?;
+
+	ctx.say(format!("Thanks! Filed as {}", issue.html_url))
+		.await?;
+
+	Ok(())
+}
+
+/// Most commands listed by `?help search`
+const MAX_HELP_SEARCH_RESULTS: usize = 20;
+
+/// Lists the commands whose name, aliases, category or help mention the keyword. Commands whose
+/// name or aliases match come first.
+async fn help_search(ctx: Context<'_>, keyword: &str) -> Result<(), Error> {
+	fn collect_commands<'a>(
+		commands: &'a [poise::Command<Data, Error>],
+		out: &mut Vec<&'a poise::Command<Data, Error>>,
+	) {
+		for command in commands {
+			if !command.hide_in_help {
+				out.push(command);
+				collect_commands(&command.subcommands, out);
+			}
+		}
+	}
+
+	let keyword = keyword.trim().to_lowercase();
+	let mut commands = Vec::new();
+	collect_commands(&ctx.framework().options().commands, &mut commands);
+
+	let mut matches = commands
+		.into_iter()
+		.filter_map(|command| {
+			let name_matches = std::iter::once(&command.qualified_name)
+				.chain(&command.aliases)
+				.any(|name| name.to_lowercase().contains(&keyword));
+			let text_matches = [
+				command.description.as_deref(),
+				command.help_text.as_deref(),
+				command.category.as_deref(),
+			]
+			.into_iter()
+			.flatten()
+			.any(|text| text.to_lowercase().contains(&keyword));
+			(name_matches || text_matches).then_some((!name_matches, command))
+		})
+		.collect::<Vec<_>>();
+	matches.sort_by(|(a_rank, a), (b_rank, b)| {
+		(a_rank, &a.qualified_name).cmp(&(b_rank, &b.qualified_name))
+	});
+
+	if matches.is_empty() {
+		bail!("No commands found for `{}`", keyword);
+	}
+
+	let mut description = matches
+		.iter()
+		.take(MAX_HELP_SEARCH_RESULTS)
+		.map(|(_, command)| {
+			format!(
+				"`?{}` – {}\n",
+				command.qualified_name,
+				command.description.as_deref().unwrap_or("no description")
+			)
+		})
+		.collect::<String>();
+	if matches.len() > MAX_HELP_SEARCH_RESULTS {
+		description += &format!(
+			"…and {} more, try a more specific keyword\n",
+			matches.len() - MAX_HELP_SEARCH_RESULTS
+		);
+	}
+
+	ctx.send(
+		poise::CreateReply::default()
+			.embed(
+				serenity::CreateEmbed::new()
+					.color(crate::types::EMBED_COLOR)
+					.title(format!("Commands matching `{}`", keyword))
+					.description(description)
+					.footer(serenity::CreateEmbedFooter::new(
+						"Type ?help command for more info on a command",
+					)),
+			)
+			.ephemeral(true),
+	)
+	.await?;
+
+	Ok(())
+}
+
 /// Show this menu
+///
+/// /help [command]
+/// /help search <keyword>
+///
+/// Shows all commands, or help about a specific one. With `search`, lists the commands whose \
+/// name or description mention a keyword.
 #[poise::command(prefix_command, slash_command, category = "Utilities", track_edits)]
 pub async fn help(
 	ctx: Context<'_>,
 	#[description = "Specific command to show help about"]
 	#[autocomplete = "poise::builtins::autocomplete_command"]
 	command: Option<String>,
+	#[description = "Keyword to search commands for"]
+	#[rest]
+	search: Option<String>,
 ) -> Result<(), Error> {
+	// With prefix commands, `?help search keyword` puts "search" into `command`
+	match (command.as_deref(), search) {
+		(None | Some("search"), Some(keyword)) => return help_search(ctx, &keyword).await,
+		(Some("search"), None) => bail!("Please give a keyword to search for"),
+		_ => {}
+	}
+
 	let extra_text_at_bottom = "\
 You can still use all commands with `?`, even if it says `/` above.
 Type ?help command for more info on a command.
+Type ?help search keyword to find commands about something.
 You can edit your message to the bot and the bot will edit its response.";
 
 	poise::builtins::help(
@@ -74,10 +278,143 @@ pub async fn register(ctx: Context<'_>) -> Result<(), Error> {
 	Ok(())
 }
 
-/// Tells you how long the bot has been up for
-#[poise::command(prefix_command, slash_command, category = "Utilities")]
-pub async fn uptime(ctx: Context<'_>) -> Result<(), Error> {
-	let uptime = std::time::Instant::now() - ctx.data().bot_start_time;
+/// Key identifying an application command: its name and type (slash, user or message command)
+fn application_command_key(command: &serde_json::Value) -> (String, u64) {
+	(
+		command["name"].as_str().unwrap_or_default().to_owned(),
+		// Slash commands may omit the type, since it's the default
+		command["type"].as_u64().unwrap_or(1),
+	)
+}
+
+/// Everything users see of an application command: its description and the names and types of
+/// its parameters, including those of subcommands
+fn application_command_signature(command: &serde_json::Value) -> Vec<String> {
+	fn collect_options(options: &serde_json::Value, prefix: &str, signature: &mut Vec<String>) {
+		for option in options.as_array().into_iter().flatten() {
+			let path = format!("{}{}", prefix, option["name"].as_str().unwrap_or_default());
+			signature.push(format!(
+				"{}:{}:{}",
+				path,
+				option["type"],
+				option["required"].as_bool().unwrap_or(false)
+			));
+			collect_options(&option["options"], &format!("{}/", path), signature);
+		}
+	}
+
+	let mut signature = vec![command["description"]
+		.as_str()
+		.unwrap_or_default()
+		.to_owned()];
+	collect_options(&command["options"], "", &mut signature);
+	signature
+}
+
+/// Compare slash commands registered with Discord to the ones the bot defines
+#[poise::command(
+	prefix_command,
+	slash_command,
+	rename = "slash-sync",
+	subcommands("slash_sync_status"),
+	subcommand_required,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_owner"
+)]
+pub async fn slash_sync(_: Context<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Lists commands that are missing, extra or outdated in this guild's registered commands
+///
+/// /slash-sync status
+///
+/// Useful when commands don't show up after a deploy. Use /register to fix any differences.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	guild_only,
+	rename = "status",
+	check = "crate::checks::check_is_owner"
+)]
+pub async fn slash_sync_status(ctx: Context<'_>) -> Result<(), Error> {
+	let guild_id = ctx
+		.guild_id()
+		.ok_or(anyhow!("This command can only be used in a server"))?;
+
+	let defined = poise::builtins::create_application_commands(&ctx.framework().options().commands)
+		.into_iter()
+		.map(|command| serde_json::to_value(command).map_err(Error::from))
+		.collect::<Result<Vec<_>, _>>()?;
+	let registered = guild_id
+		.get_commands(ctx)
+		.await?
+		.into_iter()
+		.map(|command| serde_json::to_value(command).map_err(Error::from))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let format_names = |commands: Vec<&serde_json::Value>| {
+		if commands.is_empty() {
+			"none".to_owned()
+		} else {
+			commands
+				.iter()
+				.map(|command| format!("`{}`", application_command_key(command).0))
+				.collect::<Vec<_>>()
+				.join(", ")
+		}
+	};
+	let find = |commands: &[serde_json::Value], key: &(String, u64)| {
+		commands
+			.iter()
+			.find(|command| application_command_key(command) == *key)
+			.cloned()
+	};
+
+	let missing = defined
+		.iter()
+		.filter(|command| find(&registered, &application_command_key(command)).is_none())
+		.collect::<Vec<_>>();
+	let extra = registered
+		.iter()
+		.filter(|command| find(&defined, &application_command_key(command)).is_none())
+		.collect::<Vec<_>>();
+	let mismatched = defined
+		.iter()
+		.filter(|command| {
+			find(&registered, &application_command_key(command)).is_some_and(|registered| {
+				application_command_signature(command) != application_command_signature(&registered)
+			})
+		})
+		.collect::<Vec<_>>();
+
+	let in_sync = missing.is_empty() && extra.is_empty() && mismatched.is_empty();
+	ctx.say(format!(
+		"**{} commands defined, {} registered in this guild{}**\n\
+		Missing (defined, but not registered): {}\n\
+		Extra (registered, but not defined): {}\n\
+		Outdated (description or parameters differ): {}",
+		defined.len(),
+		registered.len(),
+		if in_sync {
+			", all in sync"
+		} else {
+			". Use /register to sync them"
+		},
+		format_names(missing),
+		format_names(extra),
+		format_names(mismatched),
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Formats the time since the bot started, e.g. "1d 2h 3m 4s"
+fn format_uptime(data: &Data) -> String {
+	let uptime = std::time::Instant::now() - data.bot_start_time;
 
 	let div_mod = |a, b| (a / b, a % b);
 
@@ -86,15 +423,549 @@ pub async fn uptime(ctx: Context<'_>) -> Result<(), Error> {
 	let (hours, minutes) = div_mod(minutes, 60);
 	let (days, hours) = div_mod(hours, 24);
 
+	format!("{}d {}h {}m {}s", days, hours, minutes, seconds)
+}
+
+/// Returns how often the gateway connection was re-established and resumed, respectively
+fn reconnect_counts(data: &Data) -> (u32, u32) {
+	// The first Ready event is the initial connection, not a reconnect
+	let reconnects = data.ready_count.load(Ordering::Relaxed).saturating_sub(1);
+	let resumes = data.resume_count.load(Ordering::Relaxed);
+	(reconnects, resumes)
+}
+
+/// Tells you how long the bot has been up for
+///
+/// Also shows how often the connection to Discord was re-established, which hints at gateway
+/// instability if it happens a lot.
+#[poise::command(prefix_command, slash_command, category = "Utilities")]
+pub async fn uptime(ctx: Context<'_>) -> Result<(), Error> {
+	let (reconnects, resumes) = reconnect_counts(ctx.data());
+
+	ctx.say(format!(
+		"Uptime: {}\nReconnects: {}, resumed sessions: {}",
+		format_uptime(ctx.data()),
+		reconnects,
+		resumes
+	))
+	.await?;
+
+	Ok(())
+}
+
+/// Resident memory of the bot process, read from procfs. Only available on Linux.
+fn memory_usage() -> Option<String> {
+	let status = std::fs::read_to_string("/proc/self/status").ok()?;
+	let kilobytes: u64 = status
+		.lines()
+		.find_map(|line| line.strip_prefix("VmRSS:"))?
+		.trim()
+		.strip_suffix("kB")?
+		.trim()
+		.parse()
+		.ok()?;
+	Some(format!("{:.1} MiB", kilobytes as f64 / 1024.0))
+}
+
+/// Checks whether the database responds, and how quickly
+async fn database_status(data: &Data) -> String {
+	let start = std::time::Instant::now();
+	match sqlx::query("SELECT 1").execute(&data.database).await {
+		Ok(_) => format!("OK ({}ms)", start.elapsed().as_millis()),
+		Err(e) => format!("Error: {}", e),
+	}
+}
+
+/// Shows runtime diagnostics of the bot
+///
+/// /botinfo
+///
+/// Shows uptime, connection health, database status, cache sizes, memory usage and the running
+/// version in one place.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator"
+)]
+pub async fn botinfo(ctx: Context<'_>) -> Result<(), Error> {
+	let (reconnects, resumes) = reconnect_counts(ctx.data());
+	let cache = &ctx.serenity_context().cache;
+	let version = match option_env!("RUSTBOT_REV") {
+		Some(rev) => format!("{} ({})", env!("CARGO_PKG_VERSION"), rev.trim()),
+		None => env!("CARGO_PKG_VERSION").to_owned(),
+	};
+
+	ctx.send(
+		poise::CreateReply::default().embed(
+			serenity::CreateEmbed::new()
+				.title("Bot info")
+				.field("Uptime", format_uptime(ctx.data()), true)
+				.field(
+					"Connection",
+					format!("{} reconnects, {} resumes", reconnects, resumes),
+					true,
+				)
+				.field("Database", database_status(ctx.data()).await, true)
+				.field(
+					"Commands",
+					ctx.framework().options().commands.len().to_string(),
+					true,
+				)
+				.field(
+					"Cache",
+					format!(
+						"{} guilds, {} users",
+						cache.guild_count(),
+						cache.user_count()
+					),
+					true,
+				)
+				.field(
+					"Memory",
+					memory_usage().unwrap_or_else(|| "unknown".to_owned()),
+					true,
+				)
+				.field("Version", version, true)
+				.color(crate::types::EMBED_COLOR),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// Server statistics shown by `?serverinfo`, gathered either from the cache or via HTTP
+struct ServerInfo {
+	name: String,
+	icon_url: Option<String>,
+	member_count: u64,
+	/// `None` if not all members are cached, since the count would be misleading then
+	bot_count: Option<usize>,
+	channel_counts: std::collections::BTreeMap<&'static str, usize>,
+	premium_tier: serenity::PremiumTier,
+	boost_count: u64,
+	role_count: usize,
+	emoji_count: usize,
+}
+
+fn count_channels_by_kind<'a>(
+	channels: impl Iterator<Item = &'a serenity::GuildChannel>,
+) -> std::collections::BTreeMap<&'static str, usize> {
+	let mut counts = std::collections::BTreeMap::new();
+	for channel in channels {
+		let kind = match channel.kind {
+			serenity::ChannelType::Text => "Text",
+			serenity::ChannelType::News => "Announcement",
+			serenity::ChannelType::Voice => "Voice",
+			serenity::ChannelType::Stage => "Stage",
+			serenity::ChannelType::Forum => "Forum",
+			serenity::ChannelType::Category => "Category",
+			_ => "Other",
+		};
+		*counts.entry(kind).or_insert(0) += 1;
+	}
+	counts
+}
+
+async fn fetch_server_info(
+	ctx: Context<'_>,
+	guild_id: serenity::GuildId,
+) -> Result<ServerInfo, Error> {
+	// The cache guard must not be held across an await point, so build the info in one go
+	let cached = guild_id.to_guild_cached(&ctx).map(|guild| ServerInfo {
+		name: guild.name.clone(),
+		icon_url: guild.icon_url(),
+		member_count: guild.member_count,
+		bot_count: (guild.members.len() as u64 == guild.member_count).then(|| {
+			guild
+				.members
+				.values()
+				.filter(|member| member.user.bot)
+				.count()
+		}),
+		channel_counts: count_channels_by_kind(guild.channels.values()),
+		premium_tier: guild.premium_tier,
+		boost_count: guild.premium_subscription_count.unwrap_or(0),
+		role_count: guild.roles.len(),
+		emoji_count: guild.emojis.len(),
+	});
+	if let Some(info) = cached {
+		return Ok(info);
+	}
+
+	let guild = guild_id.to_partial_guild_with_counts(&ctx).await?;
+	let channels = guild_id.channels(&ctx).await?;
+	Ok(ServerInfo {
+		icon_url: guild.icon_url(),
+		member_count: guild.approximate_member_count.unwrap_or(0),
+		bot_count: None,
+		channel_counts: count_channels_by_kind(channels.values()),
+		premium_tier: guild.premium_tier,
+		boost_count: guild.premium_subscription_count.unwrap_or(0),
+		role_count: guild.roles.len(),
+		emoji_count: guild.emojis.len(),
+		name: guild.name,
+	})
+}
+
+/// Shows information about this server
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	broadcast_typing,
+	category = "Utilities"
+)]
+pub async fn serverinfo(ctx: Context<'_>) -> Result<(), Error> {
+	let guild_id = ctx
+		.guild_id()
+		.ok_or(anyhow!("This command can only be used in a server"))?;
+	let info = fetch_server_info(ctx, guild_id).await?;
+
+	let channels = info
+		.channel_counts
+		.iter()
+		.map(|(kind, count)| format!("{}: {}", kind, count))
+		.collect::<Vec<_>>()
+		.join("\n");
+	let bots = match info.bot_count {
+		Some(bot_count) => bot_count.to_string(),
+		None => "unknown".to_owned(),
+	};
+
+	let mut embed = serenity::CreateEmbed::new()
+		.title(&info.name)
+		.field(
+			"Created",
+			format!("<t:{}:D>", guild_id.created_at().unix_timestamp()),
+			true,
+		)
+		.field("Members", info.member_count.to_string(), true)
+		.field("Bots", bots, true)
+		.field(
+			"Boosts",
+			format!(
+				"Tier {} ({} boosts)",
+				u8::from(info.premium_tier),
+				info.boost_count
+			),
+			true,
+		)
+		.field("Roles", info.role_count.to_string(), true)
+		.field("Emojis", info.emoji_count.to_string(), true)
+		.field("Channels", channels, false)
+		.color(crate::types::EMBED_COLOR);
+	if let Some(icon_url) = info.icon_url {
+		embed = embed.thumbnail(icon_url);
+	}
+
+	ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+	Ok(())
+}
+
+/// Discord CDN URLs default to 1024px, request the largest size instead
+fn full_resolution_url(url: &str) -> String {
+	match url.split_once("?size=") {
+		Some((base, _)) => format!("{}?size=4096", base),
+		None => url.to_owned(),
+	}
+}
+
+/// Shows a user's avatar in full resolution
+#[poise::command(
+	prefix_command,
+	slash_command,
+	help_text_fn = "avatar_help",
+	category = "Utilities"
+)]
+pub async fn avatar(
+	ctx: Context<'_>,
+	#[description = "User whose avatar to show (defaults to you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+	let user = user.as_ref().unwrap_or_else(|| ctx.author());
+
+	let guild_avatar = match ctx.guild_id() {
+		Some(guild_id) => guild_id
+			.member(ctx, user.id)
+			.await
+			.ok()
+			.and_then(|member| member.avatar_url()),
+		None => None,
+	};
+	let global_avatar = full_resolution_url(&user.face());
+
+	let mut links = format!("[Download]({})", global_avatar);
+	let image = match guild_avatar {
+		Some(guild_avatar) => {
+			let guild_avatar = full_resolution_url(&guild_avatar);
+			links = format!(
+				"[Download server avatar]({}) | [Download global avatar]({})",
+				guild_avatar, global_avatar
+			);
+			guild_avatar
+		}
+		None => global_avatar,
+	};
+
+	ctx.send(
+		poise::CreateReply::default().embed(
+			serenity::CreateEmbed::new()
+				.title(format!("{}'s avatar", user.name))
+				.description(links)
+				.image(image)
+				.color(crate::types::EMBED_COLOR),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
+
+pub fn avatar_help() -> String {
+	CommandHelp {
+		summary:
+			"Shows the avatar of the given user, or your own if no user is given. If the user \
+has set a server-specific avatar, that one is shown along with a link to their global avatar.",
+		usage: "/avatar [user]",
+		details: "",
+		examples: &["?avatar", "?avatar @ferris"],
+	}
+	.render()
+}
+
+/// Shows a user's profile banner in full resolution
+///
+/// /banner [user]
+///
+/// Shows the profile banner of the given user, or your own if no user is given.
+#[poise::command(prefix_command, slash_command, category = "Utilities")]
+pub async fn banner(
+	ctx: Context<'_>,
+	#[description = "User whose banner to show (defaults to you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+	let user_id = user.as_ref().unwrap_or_else(|| ctx.author()).id;
+
+	// Banners are only sent when fetching the user via the API, cached users don't have them
+	let user = ctx.http().get_user(user_id).await?;
+	let Some(banner) = user.banner_url() else {
+		ctx.say(format!("{} doesn't have a banner.", user.name))
+			.await?;
+		return Ok(());
+	};
+	let banner = full_resolution_url(&banner);
+
+	ctx.send(
+		poise::CreateReply::default().embed(
+			serenity::CreateEmbed::new()
+				.title(format!("{}'s banner", user.name))
+				.description(format!("[Download]({})", banner))
+				.image(banner)
+				.color(crate::types::EMBED_COLOR),
+		),
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// One-line preview of a message's content for listings
+fn message_preview(ctx: Context<'_>, message: &serenity::Message) -> String {
+	const MAX_PREVIEW_LENGTH: usize = 80;
+
+	let content = message.content_safe(ctx).replace('\n', " ");
+	if content.trim().is_empty() {
+		return if message.attachments.is_empty() {
+			"_<embed>_".to_owned()
+		} else {
+			"_<attachment>_".to_owned()
+		};
+	}
+
+	let mut preview = content.chars().take(MAX_PREVIEW_LENGTH).collect::<String>();
+	if content.chars().count() > MAX_PREVIEW_LENGTH {
+		preview.push('…');
+	}
+	preview
+}
+
+/// Lists the pinned messages of this channel
+///
+/// /pin-list
+///
+/// Lists all pinned messages of the current channel with a short preview and a link to jump to
+/// each of them.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	rename = "pin-list",
+	aliases("pins"),
+	category = "Utilities"
+)]
+pub async fn pin_list(ctx: Context<'_>) -> Result<(), Error> {
+	const PINS_PER_PAGE: usize = 10;
+
+	let pins = ctx.channel_id().pins(ctx).await?;
+	if pins.is_empty() {
+		ctx.say("There are no pinned messages in this channel.")
+			.await?;
+		return Ok(());
+	}
+
+	let lines = pins
+		.iter()
+		.map(|message| {
+			format!(
+				"[Jump]({}) **{}**: {}",
+				message.link(),
+				message.author.name,
+				message_preview(ctx, message)
+			)
+		})
+		.collect::<Vec<_>>();
+	let pages = lines
+		.chunks(PINS_PER_PAGE)
+		.map(|page| page.join("\n"))
+		.collect::<Vec<_>>();
+
+	poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+
+	Ok(())
+}
+
+/// Discord allows at most this many embeds per message
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+
+/// Copies a message to another channel
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_MESSAGES",
+	help_text_fn = "forward_help",
+	category = "Utilities"
+)]
+pub async fn forward(
+	ctx: Context<'_>,
+	#[description = "Message to forward (link or ID)"] message: serenity::Message,
+	#[description = "Channel to forward the message to"] channel: serenity::GuildChannel,
+	#[description = "Publish the copy to following channels"] crosspost: Option<bool>,
+) -> Result<(), Error> {
+	// The bot can read and post in more channels than the invoker, so it mustn't be used to leak
+	// messages out of or post into channels the invoker has no access to
+	let Some(source) = message.channel(ctx).await?.guild() else {
+		bail!("Only messages from this server can be forwarded");
+	};
+	let member = ctx
+		.author_member()
+		.await
+		.ok_or(anyhow!("Couldn't fetch your server member"))?;
+	let (source_permissions, target_permissions) = {
+		let guild = ctx.guild().ok_or(anyhow!("Couldn't find the server"))?;
+		let permissions_in = |channel: &serenity::GuildChannel| {
+			// Threads inherit the permissions of their parent channel
+			let channel = channel
+				.thread_metadata
+				.and(channel.parent_id)
+				.and_then(|parent_id| guild.channels.get(&parent_id))
+				.unwrap_or(channel);
+			guild.user_permissions_in(channel, &member)
+		};
+		(permissions_in(&source), permissions_in(&channel))
+	};
+	if !source_permissions.view_channel() || !source_permissions.read_message_history() {
+		bail!("You can't read messages in {}", source.mention());
+	}
+	if !target_permissions.send_messages() || !target_permissions.manage_messages() {
+		bail!(
+			"You need the permissions to send and manage messages in {}",
+			channel.mention()
+		);
+	}
+
+	let crosspost = crosspost.unwrap_or(false);
+	if crosspost && channel.kind != serenity::ChannelType::News {
+		bail!(
+			"{} is not an announcement channel, so the message can't be crossposted",
+			channel.mention()
+		);
+	}
+
+	let mut attachments = Vec::with_capacity(message.attachments.len());
+	for attachment in &message.attachments {
+		attachments.push(serenity::CreateAttachment::url(ctx, &attachment.url).await?);
+	}
+
+	let attribution = serenity::CreateEmbed::new()
+		.color(crate::types::EMBED_COLOR)
+		.description(format!(
+			"[Jump to the original message]({})",
+			message.link()
+		))
+		.footer(
+			serenity::CreateEmbedFooter::new(format!(
+				"Originally posted by {}",
+				message.author.name
+			))
+			.icon_url(message.author.face()),
+		)
+		.timestamp(message.timestamp);
+	let embeds = message
+		.embeds
+		.iter()
+		.take(MAX_EMBEDS_PER_MESSAGE - 1)
+		.cloned()
+		.map(serenity::CreateEmbed::from)
+		.chain([attribution])
+		.collect();
+
+	let copy = channel
+		.send_message(
+			ctx,
+			serenity::CreateMessage::new()
+				.content(&message.content)
+				.embeds(embeds)
+				.files(attachments)
+				.allowed_mentions(serenity::CreateAllowedMentions::new()),
+		)
+		.await?;
+	if crosspost {
+		copy.crosspost(ctx).await?;
+	}
+
 	ctx.say(format!(
-		"Uptime: {}d {}h {}m {}s",
-		days, hours, minutes, seconds
+		"Forwarded to {}: {}",
+		channel.mention(),
+		copy.link()
 	))
 	.await?;
 
 	Ok(())
 }
 
+pub fn forward_help() -> String {
+	CommandHelp {
+		summary:
+			"Copies a message, including its attachments and embeds, to another channel, e.g. \
+to promote a good help answer to announcements. The copy credits the original author and links \
+back to the original message.",
+		usage: "/forward <message> <channel> [crosspost]",
+		details:
+			"With `crosspost`, the copy is also published to channels following the target if \
+it's an announcement channel. You need to be able to read the original message, and to send and \
+manage messages in the target channel.",
+		examples: &[
+			"?forward <message link> #announcements",
+			"?forward <message link> #announcements true",
+		],
+	}
+	.render()
+}
+
 /// Use this joke command to have Conrad Ludgate tell you to get something
 ///
 /// Example: `/conradluget a better computer`
@@ -154,27 +1025,44 @@ pub async fn conradluget(
 }
 
 /// Deletes the bot's messages for cleanup
-///
-/// /cleanup [limit]
-///
-/// By default, only the most recent bot message is deleted (limit = 1).
-///
-/// Deletes the bot's messages for cleanup.
-/// You can specify how many messages to look for. Only the 20 most recent messages within the
-/// channel from the last 24 hours can be deleted.
 #[poise::command(
 	prefix_command,
 	slash_command,
+	help_text_fn = "cleanup_help",
 	category = "Utilities",
 	on_error = "crate::helpers::acknowledge_fail"
 )]
 pub async fn cleanup(
 	ctx: Context<'_>,
 	#[description = "Number of messages to delete"] num_messages: Option<usize>,
+	#[description = "Also delete pinned messages"] include_pinned: Option<bool>,
+	#[description = "Only count the messages that would be deleted"] dry_run: Option<bool>,
 ) -> Result<(), Error> {
 	let num_messages = num_messages.unwrap_or(1);
+	let include_pinned = include_pinned.unwrap_or(false);
+
+	let messages_to_delete = cleanup_candidates(ctx, num_messages, include_pinned).await?;
+
+	if dry_run.unwrap_or(false) {
+		ctx.say(describe_cleanup_candidates(ctx, &messages_to_delete))
+			.await?;
+		return Ok(());
+	}
 
-	let messages_to_delete = ctx
+	ctx.channel_id()
+		.delete_messages(&ctx, messages_to_delete)
+		.await?;
+
+	crate::helpers::acknowledge_success(ctx, "rustOk", '👌').await
+}
+
+/// The messages `?cleanup` deletes: the most recent of the bot's messages from the last 24 hours
+async fn cleanup_candidates(
+	ctx: Context<'_>,
+	num_messages: usize,
+	include_pinned: bool,
+) -> Result<Vec<serenity::Message>, Error> {
+	Ok(ctx
 		.channel_id()
 		.messages(&ctx, serenity::GetMessages::new().limit(20))
 		.await?
@@ -182,14 +1070,61 @@ pub async fn cleanup(
 		.filter(|msg| {
 			(msg.author.id == ctx.data().application_id)
 				&& (*ctx.created_at() - *msg.timestamp).num_hours() < 24
+				// Pins are usually intentional, so they're only deleted on request
+				&& (include_pinned || !msg.pinned)
 		})
-		.take(num_messages);
+		.take(num_messages)
+		.collect())
+}
 
-	ctx.channel_id()
-		.delete_messages(&ctx, messages_to_delete)
-		.await?;
+/// Summary of what a `?cleanup` would delete, broken down by the age of the messages
+fn describe_cleanup_candidates(ctx: Context<'_>, messages: &[serenity::Message]) -> String {
+	if messages.is_empty() {
+		return "No messages would be deleted.".to_owned();
+	}
 
-	crate::helpers::acknowledge_success(ctx, "rustOk", '👌').await
+	let mut age_buckets = [
+		("under an hour old", 0),
+		("1 to 6 hours old", 0),
+		("6 to 24 hours old", 0),
+	];
+	for msg in messages {
+		let bucket = match (*ctx.created_at() - *msg.timestamp).num_hours() {
+			0 => 0,
+			1..=5 => 1,
+			_ => 2,
+		};
+		age_buckets[bucket].1 += 1;
+	}
+	let pinned = messages.iter().filter(|msg| msg.pinned).count();
+
+	let mut description = format!("{} messages would be deleted:\n", messages.len());
+	for (age, count) in age_buckets.iter().filter(|(_, count)| *count > 0) {
+		description += &format!("- {} {}\n", count, age);
+	}
+	if pinned > 0 {
+		description += &format!("{} of them are pinned\n", pinned);
+	}
+	description
+}
+
+pub fn cleanup_help() -> String {
+	CommandHelp {
+		summary: "Deletes the bot's messages for cleanup. By default, only the most recent bot \
+message is deleted.",
+		usage: "/cleanup [limit] [include_pinned] [dry_run]",
+		details:
+			"Only the 20 most recent messages within the channel from the last 24 hours can be \
+deleted. Pinned messages are skipped unless `include_pinned` is set. With `dry_run`, nothing is \
+deleted, and you're told how many messages would be instead.",
+		examples: &[
+			"?cleanup",
+			"?cleanup 3",
+			"?cleanup 3 true",
+			"?cleanup 20 false true",
+		],
+	}
+	.render()
 }
 
 /// Bans another person
@@ -219,13 +1154,16 @@ pub async fn ban(
 	Ok(())
 }
 
+/// How long after a self-timeout it can still be undone with `?selftimeout-cancel`
+const SELF_TIMEOUT_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Self-timeout yourself.
 ///
 /// /selftimeout [duration_in_hours] [duration_in_minutes]
 ///
 /// Self-timeout yourself.
 /// You can specify how long you want to timeout yourself for, either in hours
-/// or in minutes.
+/// or in minutes. If it was an accident, DM the bot `?selftimeout-cancel` within a minute.
 #[poise::command(
 	slash_command,
 	category = "Utilities",
@@ -254,10 +1192,20 @@ pub async fn selftimeout(
 	member
 		.disable_communication_until_datetime(&ctx, then)
 		.await?;
+	{
+		let mut recent_self_timeouts = ctx.data().recent_self_timeouts.lock().unwrap();
+		recent_self_timeouts
+			.retain(|_, (_, timed_out_at, _)| timed_out_at.elapsed() <= SELF_TIMEOUT_GRACE_PERIOD);
+		recent_self_timeouts.insert(
+			ctx.author().id,
+			(member.guild_id, std::time::Instant::now(), then),
+		);
+	}
 
 	ctx.say(format!(
 		"Self-timeout for {}. They'll be able to interact with the server again <t:{}:R>. \
-		If this was a mistake, please contact a moderator or try to enjoy the time off.",
+		If this was a mistake, DM me `?selftimeout-cancel` within a minute. After that, please \
+		contact a moderator or try to enjoy the time off.",
 		ctx.author().name,
 		then.unix_timestamp()
 	))
@@ -265,3 +1213,181 @@ pub async fn selftimeout(
 
 	Ok(())
 }
+
+/// Undo an accidental self-timeout
+///
+/// /selftimeout-cancel
+///
+/// Lifts the timeout you gave yourself with /selftimeout, if that was less than a minute ago. \
+/// Works in DMs with the bot too, since you can't post in the server while timed out. Later on, \
+/// only moderators can lift it.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	rename = "selftimeout-cancel",
+	category = "Utilities"
+)]
+pub async fn selftimeout_cancel(ctx: Context<'_>) -> Result<(), Error> {
+	let recent_self_timeout = ctx
+		.data()
+		.recent_self_timeouts
+		.lock()
+		.unwrap()
+		.remove(&ctx.author().id);
+	let Some((guild_id, timed_out_at, timed_out_until)) = recent_self_timeout else {
+		bail!("You haven't timed yourself out recently");
+	};
+	if timed_out_at.elapsed() > SELF_TIMEOUT_GRACE_PERIOD {
+		bail!("It's been more than a minute since your self-timeout, please contact a moderator");
+	}
+
+	let mut member = guild_id.member(ctx, ctx.author().id).await?;
+	// A moderator may have timed them out in the meantime, which must not be lifted this way
+	if member.communication_disabled_until != Some(timed_out_until) {
+		bail!("Your timeout was changed since your self-timeout, please contact a moderator");
+	}
+	member.enable_communication(ctx).await?;
+
+	ctx.say("Your self-timeout was lifted.").await?;
+
+	Ok(())
+}
+
+/// Commands that may never be disabled, so that disabling can always be undone
+const UNDISABLEABLE_COMMANDS: &[&str] = &["disable", "enable", "disabled"];
+
+/// Resolves a user-supplied command name (which may be an alias) to the command's qualified name
+pub(crate) fn resolve_command_name(ctx: Context<'_>, command_name: &str) -> Result<String, Error> {
+	let (command, _, _) = poise::find_command(
+		&ctx.framework().options().commands,
+		command_name.trim().trim_start_matches(['?', '/']),
+		true,
+		&mut Vec::new(),
+	)
+	.ok_or(anyhow!("Unknown command `{}`", command_name))?;
+	Ok(command.qualified_name.clone())
+}
+
+/// Temporarily disable a command
+///
+/// /disable <command>
+///
+/// Disables a command for everyone until it is re-enabled with /enable. Useful to shut down a
+/// misbehaving or abused command without redeploying the bot. Disabled commands persist across
+/// restarts.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn disable(
+	ctx: Context<'_>,
+	#[description = "Command to disable"]
+	#[autocomplete = "poise::builtins::autocomplete_command"]
+	#[rest]
+	command: String,
+) -> Result<(), Error> {
+	let command_name = resolve_command_name(ctx, &command)?;
+	if UNDISABLEABLE_COMMANDS.contains(&command_name.as_str()) {
+		bail!("`{}` can't be disabled", command_name);
+	}
+
+	let newly_disabled = crate::database::disabled_commands::disable(
+		&ctx.data().database,
+		&command_name,
+		ctx.author().id,
+	)
+	.await?;
+	ctx.data().refresh_disabled_commands().await?;
+
+	if newly_disabled {
+		ctx.say(format!(
+			"Disabled `{0}`. Use `?enable {0}` to enable it again.",
+			command_name
+		))
+		.await?;
+	} else {
+		ctx.say(format!("`{}` is already disabled.", command_name))
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// Re-enable a disabled command
+///
+/// /enable <command>
+///
+/// Re-enables a command that was disabled with /disable.
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn enable(
+	ctx: Context<'_>,
+	#[description = "Command to enable"]
+	#[autocomplete = "poise::builtins::autocomplete_command"]
+	#[rest]
+	command: String,
+) -> Result<(), Error> {
+	let command_name = resolve_command_name(ctx, &command)?;
+
+	let newly_enabled =
+		crate::database::disabled_commands::enable(&ctx.data().database, &command_name).await?;
+	ctx.data().refresh_disabled_commands().await?;
+
+	if newly_enabled {
+		ctx.say(format!("Enabled `{}` again.", command_name))
+			.await?;
+	} else {
+		ctx.say(format!("`{}` isn't disabled.", command_name))
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// List all disabled commands
+#[poise::command(
+	prefix_command,
+	slash_command,
+	ephemeral,
+	category = "Utilities",
+	hide_in_help,
+	check = "crate::checks::check_is_moderator",
+	check = "crate::checks::check_database_available"
+)]
+pub async fn disabled(ctx: Context<'_>) -> Result<(), Error> {
+	let disabled_commands =
+		crate::database::disabled_commands::get_all(&ctx.data().database).await?;
+
+	if disabled_commands.is_empty() {
+		ctx.say("No commands are disabled.").await?;
+		return Ok(());
+	}
+
+	let list = disabled_commands
+		.iter()
+		.map(|command| {
+			format!(
+				"- `{}`, disabled by <@{}> <t:{}:R>",
+				command.command_name,
+				command.disabled_by,
+				command.disabled_at.timestamp()
+			)
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+	ctx.say(format!("Disabled commands:\n{}", list)).await?;
+
+	Ok(())
+}
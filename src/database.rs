@@ -0,0 +1,109 @@
+//! Queries against the bot's Postgres database. Migrations live in `migrations/` and are applied
+//! on startup.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Error;
+use sqlx::PgPool;
+
+pub mod auto_delete_invocations;
+pub mod coc_acceptances;
+pub mod disabled_commands;
+pub mod mention_notifications;
+pub mod message_counts;
+pub mod modmail_reporters;
+pub mod server_icons;
+pub mod thread_subscriptions;
+
+/// This many failed connections within [`FAILURE_WINDOW`] mark the database as unavailable
+const FAILURES_UNTIL_UNAVAILABLE: usize = 3;
+const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long commands that need the database are refused before the database is tried again
+const UNAVAILABLE_DURATION: Duration = Duration::from_secs(30);
+
+/// Message shown instead of raw database errors
+pub const UNAVAILABLE_MESSAGE: &str =
+	"The database is temporarily unavailable, please try again in a bit.";
+
+/// Circuit breaker for the database. When the database keeps failing, commands that need it are
+/// refused right away for a while, instead of each of them waiting for the connection to time
+/// out. Afterwards, the database is pinged before letting commands through again.
+#[derive(Debug, Default)]
+pub struct DatabaseHealth {
+	state: Mutex<DatabaseHealthState>,
+}
+
+#[derive(Debug, Default)]
+struct DatabaseHealthState {
+	recent_failures: Vec<Instant>,
+	unavailable_until: Option<Instant>,
+}
+
+impl DatabaseHealth {
+	pub fn record_failure(&self) {
+		let mut state = self.state.lock().unwrap();
+		state
+			.recent_failures
+			.retain(|failure| failure.elapsed() < FAILURE_WINDOW);
+		state.recent_failures.push(Instant::now());
+
+		if state.recent_failures.len() >= FAILURES_UNTIL_UNAVAILABLE {
+			tracing::warn!(
+				"Database failed {} times within {:?}, refusing commands that need it for {:?}",
+				state.recent_failures.len(),
+				FAILURE_WINDOW,
+				UNAVAILABLE_DURATION
+			);
+			state.recent_failures.clear();
+			state.unavailable_until = Some(Instant::now() + UNAVAILABLE_DURATION);
+		}
+	}
+
+	/// Returns whether commands that need the database should be let through. Once the database
+	/// was unavailable for [`UNAVAILABLE_DURATION`], it's pinged to see whether it recovered.
+	pub async fn is_available(&self, pool: &PgPool) -> bool {
+		let unavailable_until = self.state.lock().unwrap().unavailable_until;
+		match unavailable_until {
+			None => true,
+			Some(until) if Instant::now() < until => false,
+			Some(_) => {
+				let recovered = tokio::time::timeout(
+					Duration::from_secs(5),
+					sqlx::query("SELECT 1").execute(pool),
+				)
+				.await
+				.is_ok_and(|result| result.is_ok());
+
+				let mut state = self.state.lock().unwrap();
+				if recovered {
+					tracing::info!("Database is available again");
+					state.unavailable_until = None;
+				} else {
+					state.unavailable_until = Some(Instant::now() + UNAVAILABLE_DURATION);
+				}
+				recovered
+			}
+		}
+	}
+}
+
+/// Returns the database error behind an error, if it was caused by one
+pub fn database_error(error: &Error) -> Option<&sqlx::Error> {
+	error.chain().find_map(|cause| cause.downcast_ref())
+}
+
+/// Whether a database error means that the database can't be reached, as opposed to a problem
+/// with a specific query
+pub fn is_connection_error(error: &sqlx::Error) -> bool {
+	matches!(
+		error,
+		sqlx::Error::Io(_)
+			| sqlx::Error::Tls(_)
+			| sqlx::Error::Protocol(_)
+			| sqlx::Error::PoolTimedOut
+			| sqlx::Error::PoolClosed
+			| sqlx::Error::WorkerCrashed
+	)
+}
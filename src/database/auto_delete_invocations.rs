@@ -0,0 +1,56 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct AutoDeleteInvocation {
+	pub channel_id: i64,
+	pub command_name: String,
+	pub configured_by: i64,
+	pub configured_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn get_all(pool: &PgPool) -> Result<Vec<AutoDeleteInvocation>, Error> {
+	let settings = sqlx::query_as::<_, AutoDeleteInvocation>(
+		"SELECT channel_id, command_name, configured_by, configured_at \
+		FROM auto_delete_invocations ORDER BY channel_id, command_name",
+	)
+	.fetch_all(pool)
+	.await?;
+	Ok(settings)
+}
+
+/// Returns whether the setting was newly added, i.e. `false` if it already existed
+pub async fn add(
+	pool: &PgPool,
+	channel_id: serenity::ChannelId,
+	command_name: &str,
+	configured_by: serenity::UserId,
+) -> Result<bool, Error> {
+	let result = sqlx::query(
+		"INSERT INTO auto_delete_invocations (channel_id, command_name, configured_by) \
+		VALUES ($1, $2, $3) ON CONFLICT (channel_id, command_name) DO NOTHING",
+	)
+	.bind(channel_id.get() as i64)
+	.bind(command_name)
+	.bind(configured_by.get() as i64)
+	.execute(pool)
+	.await?;
+	Ok(result.rows_affected() > 0)
+}
+
+/// Returns whether the setting was removed, i.e. `false` if it didn't exist
+pub async fn remove(
+	pool: &PgPool,
+	channel_id: serenity::ChannelId,
+	command_name: &str,
+) -> Result<bool, Error> {
+	let result = sqlx::query(
+		"DELETE FROM auto_delete_invocations WHERE channel_id = $1 AND command_name = $2",
+	)
+	.bind(channel_id.get() as i64)
+	.bind(command_name)
+	.execute(pool)
+	.await?;
+	Ok(result.rows_affected() > 0)
+}
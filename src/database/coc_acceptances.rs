@@ -0,0 +1,23 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+/// Records that the user accepted the code of conduct. Accepting again is a no-op.
+pub async fn accept(pool: &PgPool, user_id: serenity::UserId) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO coc_acceptances (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING",
+	)
+	.bind(user_id.get() as i64)
+	.execute(pool)
+	.await?;
+	Ok(())
+}
+
+pub async fn has_accepted(pool: &PgPool, user_id: serenity::UserId) -> Result<bool, Error> {
+	let accepted =
+		sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM coc_acceptances WHERE user_id = $1)")
+			.bind(user_id.get() as i64)
+			.fetch_one(pool)
+			.await?;
+	Ok(accepted)
+}
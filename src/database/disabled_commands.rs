@@ -0,0 +1,45 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct DisabledCommand {
+	pub command_name: String,
+	pub disabled_by: i64,
+	pub disabled_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn get_all(pool: &PgPool) -> Result<Vec<DisabledCommand>, Error> {
+	let commands = sqlx::query_as::<_, DisabledCommand>(
+		"SELECT command_name, disabled_by, disabled_at FROM disabled_commands ORDER BY command_name",
+	)
+	.fetch_all(pool)
+	.await?;
+	Ok(commands)
+}
+
+/// Returns whether the command was newly disabled, i.e. `false` if it was already disabled
+pub async fn disable(
+	pool: &PgPool,
+	command_name: &str,
+	disabled_by: serenity::UserId,
+) -> Result<bool, Error> {
+	let result = sqlx::query(
+		"INSERT INTO disabled_commands (command_name, disabled_by) VALUES ($1, $2) \
+		ON CONFLICT (command_name) DO NOTHING",
+	)
+	.bind(command_name)
+	.bind(disabled_by.get() as i64)
+	.execute(pool)
+	.await?;
+	Ok(result.rows_affected() > 0)
+}
+
+/// Returns whether the command was newly enabled, i.e. `false` if it wasn't disabled
+pub async fn enable(pool: &PgPool, command_name: &str) -> Result<bool, Error> {
+	let result = sqlx::query("DELETE FROM disabled_commands WHERE command_name = $1")
+		.bind(command_name)
+		.execute(pool)
+		.await?;
+	Ok(result.rows_affected() > 0)
+}
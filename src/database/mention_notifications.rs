@@ -0,0 +1,40 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct MentionNotification {
+	pub user_id: i64,
+	/// `"idle"` or `"away"`, see [`crate::commands::mentions::MentionDmMode`]
+	pub mode: String,
+	pub enabled_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn get_all(pool: &PgPool) -> Result<Vec<MentionNotification>, Error> {
+	let notifications = sqlx::query_as::<_, MentionNotification>(
+		"SELECT user_id, mode, enabled_at FROM mention_notifications",
+	)
+	.fetch_all(pool)
+	.await?;
+	Ok(notifications)
+}
+
+pub async fn set(pool: &PgPool, user_id: serenity::UserId, mode: &str) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO mention_notifications (user_id, mode) VALUES ($1, $2) \
+		ON CONFLICT (user_id) DO UPDATE SET mode = EXCLUDED.mode, enabled_at = now()",
+	)
+	.bind(user_id.get() as i64)
+	.bind(mode)
+	.execute(pool)
+	.await?;
+	Ok(())
+}
+
+pub async fn remove(pool: &PgPool, user_id: serenity::UserId) -> Result<(), Error> {
+	sqlx::query("DELETE FROM mention_notifications WHERE user_id = $1")
+		.bind(user_id.get() as i64)
+		.execute(pool)
+		.await?;
+	Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+/// Counts older than this many days are deleted when flushing
+const RETENTION_DAYS: i32 = 90;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct MessageCount {
+	pub user_id: i64,
+	pub count: i64,
+}
+
+/// Adds the given message counts to today's tallies in a single query, and drops expired tallies.
+/// Counts of users who opted out are skipped, in case they opted out after the counts were taken.
+pub async fn add_to_today(pool: &PgPool, counts: &[(serenity::UserId, u32)]) -> Result<(), Error> {
+	let user_ids = counts
+		.iter()
+		.map(|(user_id, _)| user_id.get() as i64)
+		.collect::<Vec<_>>();
+	let counts = counts
+		.iter()
+		.map(|&(_, count)| count as i32)
+		.collect::<Vec<_>>();
+
+	sqlx::query(
+		"INSERT INTO message_counts (user_id, day, count) \
+		SELECT user_id, CURRENT_DATE, count FROM UNNEST($1::BIGINT[], $2::INTEGER[]) AS t(user_id, count) \
+		WHERE user_id NOT IN (SELECT user_id FROM leaderboard_opt_outs) \
+		ON CONFLICT (user_id, day) DO UPDATE SET count = message_counts.count + EXCLUDED.count",
+	)
+	.bind(user_ids)
+	.bind(counts)
+	.execute(pool)
+	.await?;
+
+	sqlx::query("DELETE FROM message_counts WHERE day < CURRENT_DATE - $1")
+		.bind(RETENTION_DAYS)
+		.execute(pool)
+		.await?;
+
+	Ok(())
+}
+
+/// The `limit` users with the most messages in the last `days` days, including today
+pub async fn top_users(pool: &PgPool, days: u32, limit: u32) -> Result<Vec<MessageCount>, Error> {
+	let counts = sqlx::query_as::<_, MessageCount>(
+		"SELECT user_id, SUM(count)::BIGINT AS count FROM message_counts \
+		WHERE day > CURRENT_DATE - $1 \
+		GROUP BY user_id ORDER BY count DESC LIMIT $2",
+	)
+	.bind(days as i32)
+	.bind(i64::from(limit))
+	.fetch_all(pool)
+	.await?;
+	Ok(counts)
+}
+
+pub async fn get_opt_outs(pool: &PgPool) -> Result<Vec<i64>, Error> {
+	let user_ids = sqlx::query_scalar("SELECT user_id FROM leaderboard_opt_outs")
+		.fetch_all(pool)
+		.await?;
+	Ok(user_ids)
+}
+
+/// Opts the user out of message counting and deletes their existing counts
+pub async fn opt_out(pool: &PgPool, user_id: serenity::UserId) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO leaderboard_opt_outs (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING",
+	)
+	.bind(user_id.get() as i64)
+	.execute(pool)
+	.await?;
+	sqlx::query("DELETE FROM message_counts WHERE user_id = $1")
+		.bind(user_id.get() as i64)
+		.execute(pool)
+		.await?;
+	Ok(())
+}
+
+pub async fn opt_in(pool: &PgPool, user_id: serenity::UserId) -> Result<(), Error> {
+	sqlx::query("DELETE FROM leaderboard_opt_outs WHERE user_id = $1")
+		.bind(user_id.get() as i64)
+		.execute(pool)
+		.await?;
+	Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ModmailReporter {
+	pub thread_id: i64,
+	pub user_id: i64,
+	pub anonymous: bool,
+	pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn insert(
+	pool: &PgPool,
+	thread_id: serenity::ChannelId,
+	user_id: serenity::UserId,
+	anonymous: bool,
+) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO modmail_reporters (thread_id, user_id, anonymous) VALUES ($1, $2, $3)",
+	)
+	.bind(thread_id.get() as i64)
+	.bind(user_id.get() as i64)
+	.bind(anonymous)
+	.execute(pool)
+	.await?;
+	Ok(())
+}
+
+pub async fn get(
+	pool: &PgPool,
+	thread_id: serenity::ChannelId,
+) -> Result<Option<ModmailReporter>, Error> {
+	let reporter = sqlx::query_as::<_, ModmailReporter>(
+		"SELECT thread_id, user_id, anonymous, created_at FROM modmail_reporters \
+		WHERE thread_id = $1",
+	)
+	.bind(thread_id.get() as i64)
+	.fetch_optional(pool)
+	.await?;
+	Ok(reporter)
+}
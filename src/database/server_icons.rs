@@ -0,0 +1,31 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+/// File name of the icon the bot last set for the guild, if any
+pub async fn get_current(
+	pool: &PgPool,
+	guild_id: serenity::GuildId,
+) -> Result<Option<String>, Error> {
+	let icon_name = sqlx::query_scalar("SELECT icon_name FROM server_icons WHERE guild_id = $1")
+		.bind(guild_id.get() as i64)
+		.fetch_optional(pool)
+		.await?;
+	Ok(icon_name)
+}
+
+pub async fn set_current(
+	pool: &PgPool,
+	guild_id: serenity::GuildId,
+	icon_name: &str,
+) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO server_icons (guild_id, icon_name) VALUES ($1, $2) \
+		ON CONFLICT (guild_id) DO UPDATE SET icon_name = EXCLUDED.icon_name, changed_at = now()",
+	)
+	.bind(guild_id.get() as i64)
+	.bind(icon_name)
+	.execute(pool)
+	.await?;
+	Ok(())
+}
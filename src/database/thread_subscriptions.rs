@@ -0,0 +1,70 @@
+use anyhow::Error;
+use poise::serenity_prelude as serenity;
+use sqlx::PgPool;
+
+/// Subscribes the user to the thread until `expires_at`, or extends an existing subscription
+pub async fn subscribe(
+	pool: &PgPool,
+	thread_id: serenity::ChannelId,
+	user_id: serenity::UserId,
+	expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<(), Error> {
+	sqlx::query(
+		"INSERT INTO thread_subscriptions (thread_id, user_id, expires_at) VALUES ($1, $2, $3) \
+		ON CONFLICT (thread_id, user_id) DO UPDATE SET expires_at = EXCLUDED.expires_at",
+	)
+	.bind(thread_id.get() as i64)
+	.bind(user_id.get() as i64)
+	.bind(expires_at)
+	.execute(pool)
+	.await?;
+	Ok(())
+}
+
+/// Returns whether the user was subscribed
+pub async fn unsubscribe(
+	pool: &PgPool,
+	thread_id: serenity::ChannelId,
+	user_id: serenity::UserId,
+) -> Result<bool, Error> {
+	let result =
+		sqlx::query("DELETE FROM thread_subscriptions WHERE thread_id = $1 AND user_id = $2")
+			.bind(thread_id.get() as i64)
+			.bind(user_id.get() as i64)
+			.execute(pool)
+			.await?;
+	Ok(result.rows_affected() > 0)
+}
+
+/// Deletes expired subscriptions and returns the threads that still have subscribers
+pub async fn active_thread_ids(pool: &PgPool) -> Result<Vec<i64>, Error> {
+	sqlx::query("DELETE FROM thread_subscriptions WHERE expires_at <= now()")
+		.execute(pool)
+		.await?;
+	let thread_ids = sqlx::query_scalar("SELECT DISTINCT thread_id FROM thread_subscriptions")
+		.fetch_all(pool)
+		.await?;
+	Ok(thread_ids)
+}
+
+/// Returns the subscribers of the thread that are due a notification about a new message by
+/// `author_id`, i.e. weren't notified within the cooldown, and marks them as notified
+pub async fn take_due_subscribers(
+	pool: &PgPool,
+	thread_id: serenity::ChannelId,
+	author_id: serenity::UserId,
+	cooldown: std::time::Duration,
+) -> Result<Vec<i64>, Error> {
+	let user_ids = sqlx::query_scalar(
+		"UPDATE thread_subscriptions SET last_notified_at = now() \
+		WHERE thread_id = $1 AND user_id <> $2 AND expires_at > now() \
+		AND (last_notified_at IS NULL OR last_notified_at < now() - make_interval(secs => $3)) \
+		RETURNING user_id",
+	)
+	.bind(thread_id.get() as i64)
+	.bind(author_id.get() as i64)
+	.bind(cooldown.as_secs_f64())
+	.fetch_all(pool)
+	.await?;
+	Ok(user_ids)
+}
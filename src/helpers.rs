@@ -1,8 +1,8 @@
-use anyhow::Error;
+use anyhow::{anyhow, bail, Error};
 use poise::serenity_prelude as serenity;
 use tracing::warn;
 
-use crate::types::{Context, Data};
+use crate::types::{Context, Data, InvocationState};
 
 /// Used for playground stdout + stderr, or godbolt asm + stderr
 /// If the return value is empty, returns " " instead, because Discord displays those better in
@@ -96,6 +96,15 @@ pub async fn acknowledge_success(
 	Ok(())
 }
 
+const MAX_OUTPUT_LINES: usize = 45;
+const MAX_OUTPUT_LENGTH: usize = 2000;
+
+/// Whether [`trim_text`] truncates this text
+pub fn needs_trimming(text_body: &str, text_end: &str) -> bool {
+	text_body.len() + text_end.len() > MAX_OUTPUT_LENGTH
+		|| text_body.lines().count() > MAX_OUTPUT_LINES
+}
+
 /// Truncates the message with a given truncation message if the
 /// text is too long. "Too long" means, it either goes beyond Discord's 2000 char message limit,
 /// or if the text_body has too many lines.
@@ -108,13 +117,7 @@ pub async fn trim_text(
 	text_end: &str,
 	truncation_msg_future: impl std::future::Future<Output = String>,
 ) -> String {
-	const MAX_OUTPUT_LINES: usize = 45;
-	const MAX_OUTPUT_LENGTH: usize = 2000;
-
-	let needs_truncating = text_body.len() + text_end.len() > MAX_OUTPUT_LENGTH
-		|| text_body.lines().count() > MAX_OUTPUT_LINES;
-
-	if needs_truncating {
+	if needs_trimming(text_body, text_end) {
 		let truncation_msg = truncation_msg_future.await;
 
 		// truncate for length
@@ -146,3 +149,339 @@ pub async fn reply_potentially_long_text(
 		.await?;
 	Ok(())
 }
+
+/// Finds the first Rust code block in a message, i.e. one tagged as `rust`/`rs` or untagged
+pub async fn find_rust_code_block(
+	ctx: &serenity::Context,
+	msg: &serenity::Message,
+) -> Option<poise::CodeBlock> {
+	use poise::PopArgument as _;
+
+	let mut rest = msg.content.as_str();
+	while let Some(start) = rest.find("```") {
+		let Ok((remaining, _, code_block)) =
+			poise::CodeBlock::pop_from(&rest[start..], 0, ctx, msg).await
+		else {
+			break;
+		};
+		if matches!(code_block.language.as_deref(), None | Some("rust" | "rs")) {
+			return Some(code_block);
+		}
+		rest = remaining;
+	}
+	None
+}
+
+/// Returns the code block passed to the command. If there is none and the invocation is a reply,
+/// the first Rust code block of the replied-to message is used instead, so users can just reply
+/// "?play" to some code.
+pub async fn code_block_or_replied(
+	ctx: Context<'_>,
+	code: Option<poise::CodeBlock>,
+) -> Result<poise::CodeBlock, Error> {
+	if let Some(code) = code {
+		return Ok(code);
+	}
+
+	let referenced_message = match ctx {
+		Context::Prefix(prefix_context) => prefix_context.msg.referenced_message.as_deref(),
+		Context::Application(_) => None,
+	};
+	let Some(referenced_message) = referenced_message else {
+		bail!("Missing code block. Either include one, or reply to a message that contains one.");
+	};
+
+	find_rust_code_block(ctx.serenity_context(), referenced_message)
+		.await
+		.ok_or(anyhow!(
+			"The message you replied to doesn't contain a Rust code block"
+		))
+}
+
+/// Position of the highest role of the member, where 0 means only @everyone
+fn highest_role_position(guild: &serenity::PartialGuild, member: &serenity::Member) -> u16 {
+	member
+		.roles
+		.iter()
+		.filter_map(|role_id| guild.roles.get(role_id))
+		.map(|role| role.position)
+		.max()
+		.unwrap_or(0)
+}
+
+/// Positions of the invoking moderator and the bot in the role hierarchy, to check that both are
+/// above the members they act on. Moderation commands should check this before acting, to fail
+/// with a clear message instead of a confusing Discord permission error, and to prevent
+/// moderating someone of equal or higher rank. Fetched once, so that commands acting on many
+/// members don't refetch the guild for each.
+pub struct RoleHierarchy {
+	guild: serenity::PartialGuild,
+	/// `None` if the invoker is the server owner, who can moderate everyone
+	invoker_position: Option<u16>,
+	bot_position: u16,
+}
+
+impl RoleHierarchy {
+	pub async fn fetch(ctx: Context<'_>) -> Result<Self, Error> {
+		let guild = ctx
+			.guild_id()
+			.ok_or(anyhow!("This command can only be used in a server"))?
+			.to_partial_guild(ctx)
+			.await?;
+
+		let invoker_position = if ctx.author().id == guild.owner_id {
+			None
+		} else {
+			let invoker = ctx
+				.author_member()
+				.await
+				.ok_or(anyhow!("Failed to fetch server member."))?;
+			Some(highest_role_position(&guild, &invoker))
+		};
+		let bot = guild.id.member(ctx, ctx.framework().bot_id).await?;
+		let bot_position = highest_role_position(&guild, &bot);
+
+		Ok(Self {
+			guild,
+			invoker_position,
+			bot_position,
+		})
+	}
+
+	/// Checks that both the invoker and the bot are above the target
+	pub fn can_act_on(&self, target: &serenity::Member) -> Result<(), Error> {
+		if target.user.id == self.guild.owner_id {
+			bail!("You can't moderate the server owner");
+		}
+
+		let target_position = highest_role_position(&self.guild, target);
+		if self
+			.invoker_position
+			.is_some_and(|invoker_position| invoker_position <= target_position)
+		{
+			bail!("You can't moderate someone with an equal or higher role");
+		}
+		if self.bot_position <= target_position {
+			bail!("I can't moderate someone with an equal or higher role than mine");
+		}
+
+		Ok(())
+	}
+}
+
+/// Splits text into code blocks that each fit into a single Discord message. Lines are kept
+/// whole where possible; only lines that wouldn't fit into a message on their own get cut.
+pub fn split_into_codeblocks(text: &str, lang: &str) -> Vec<String> {
+	const MAX_MESSAGE_LENGTH: usize = 2000;
+	let max_body_length = MAX_MESSAGE_LENGTH - "```\n\n```".len() - lang.chars().count();
+
+	let mut bodies = Vec::new();
+	let mut current = String::new();
+	let mut current_length = 0;
+	for line in text.lines() {
+		let line = line.chars().collect::<Vec<_>>();
+		for piece in line
+			.chunks(max_body_length.max(1))
+			.chain(line.is_empty().then_some(&[][..]))
+		{
+			if current_length + piece.len() + 1 > max_body_length && !current.is_empty() {
+				bodies.push(std::mem::take(&mut current));
+				current_length = 0;
+			}
+			current.extend(piece);
+			current.push('\n');
+			current_length += piece.len() + 1;
+		}
+	}
+	if !current.is_empty() {
+		bodies.push(current);
+	}
+
+	bodies
+		.into_iter()
+		.map(|body| format!("```{lang}\n{body}```"))
+		.collect()
+}
+
+/// Discord rejects thread names longer than this
+pub const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Starts a public thread on the given message and posts the given messages into it. Mentions in
+/// the messages are rendered but don't ping anyone.
+pub async fn post_in_new_thread(
+	cache_http: impl serenity::CacheHttp,
+	message: &serenity::Message,
+	thread_name: &str,
+	messages: &[String],
+) -> Result<serenity::GuildChannel, Error> {
+	let thread_name = thread_name
+		.chars()
+		.take(MAX_THREAD_NAME_LENGTH)
+		.collect::<String>();
+	let thread = message
+		.channel_id
+		.create_thread_from_message(
+			&cache_http,
+			message.id,
+			serenity::CreateThread::new(thread_name),
+		)
+		.await?;
+
+	for content in messages {
+		thread
+			.send_message(
+				&cache_http,
+				serenity::CreateMessage::new()
+					.content(content)
+					.allowed_mentions(serenity::CreateAllowedMentions::new()),
+			)
+			.await?;
+	}
+
+	Ok(thread)
+}
+
+/// Message content with mentions made harmless, cut off after `max_length` characters. Used to
+/// quote messages in notifications.
+pub fn quote_message(
+	ctx: &serenity::Context,
+	message: &serenity::Message,
+	max_length: usize,
+) -> String {
+	let content = message.content_safe(ctx);
+	let mut quote = content.chars().take(max_length).collect::<String>();
+	if content.chars().count() > max_length {
+		quote.push('…');
+	}
+	quote
+}
+
+/// DMs a notification embed to a user. Failures are only logged, since they mostly mean the user
+/// doesn't accept DMs from server members.
+pub async fn send_notification_dm(
+	ctx: &serenity::Context,
+	user_id: serenity::UserId,
+	embed: serenity::CreateEmbed,
+) {
+	let result = async {
+		let dm_channel = user_id.create_dm_channel(ctx).await?;
+		dm_channel
+			.send_message(ctx, serenity::CreateMessage::new().embed(embed))
+			.await
+	}
+	.await;
+	if let Err(e) = result {
+		warn!("Couldn't send notification DM to {}: {}", user_id, e);
+	}
+}
+
+/// Assembles code split across several code blocks into a single compilation unit. The first
+/// block is the crate root; every further block becomes a module named by a `// name.rs` or
+/// `// mod name` comment on its first line, or `mod_1`, `mod_2`, ... otherwise. Modules that the
+/// root declares with `mod name;` are inlined there, the others are appended as `pub mod`s.
+pub fn assemble_modules(root: &str, modules: &[poise::CodeBlock]) -> Result<String, Error> {
+	let mut assembled = root.to_owned();
+	for (i, module) in modules.iter().enumerate() {
+		let first_line = module.code.lines().next().unwrap_or_default().trim();
+		let name = first_line
+			.strip_prefix("//")
+			.map(|comment| comment.trim())
+			.and_then(|comment| {
+				comment
+					.strip_suffix(".rs")
+					.or_else(|| comment.strip_prefix("mod "))
+			})
+			.map(|name| name.trim().to_owned())
+			.unwrap_or_else(|| format!("mod_{}", i + 1));
+		if syn::parse_str::<syn::Ident>(&name).is_err() {
+			bail!("`{}` isn't a valid module name", name);
+		}
+
+		let declarations = [format!("pub mod {};", name), format!("mod {};", name)];
+		match declarations
+			.iter()
+			.find(|declaration| assembled.contains(declaration.as_str()))
+		{
+			Some(declaration) => {
+				let visibility = declaration.trim_end_matches(&format!("mod {};", name));
+				let inline = format!("{}mod {} {{\n{}\n}}", visibility, name, module.code);
+				assembled = assembled.replacen(declaration.as_str(), &inline, 1);
+			}
+			None => assembled += &format!("\n\npub mod {} {{\n{}\n}}\n", name, module.code),
+		}
+	}
+	Ok(assembled)
+}
+
+/// Help text of a command, split into parts so that all commands' help reads the same. Rendered
+/// through poise's `help_text_fn`, so it shows up both in `?help <command>` and when the
+/// arguments of a command fail to parse.
+pub struct CommandHelp<'a> {
+	/// What the command does, a sentence or two
+	pub summary: &'a str,
+	/// How to invoke the command, e.g. `?avatar [user]`
+	pub usage: &'a str,
+	/// Further explanation, e.g. of optional arguments. May be empty.
+	pub details: &'a str,
+	/// Example invocations, ideally ones that work when copied as they are
+	pub examples: &'a [&'a str],
+}
+
+impl CommandHelp<'_> {
+	pub fn render(&self) -> String {
+		let mut help = format!("{}\n```\n{}\n```\n", self.summary, self.usage.trim());
+		if !self.details.is_empty() {
+			help += self.details.trim();
+			help += "\n";
+		}
+		if !self.examples.is_empty() {
+			help += "Examples:\n```\n";
+			for example in self.examples {
+				help += example;
+				help += "\n";
+			}
+			help += "```\n";
+		}
+		help
+	}
+}
+
+/// Most compile requests to play.rust-lang.org and Godbolt that may run at the same time. Further
+/// ones wait in a queue, so load spikes don't overwhelm those services or run into rate limits.
+pub const MAX_CONCURRENT_COMPILE_REQUESTS: usize = 4;
+
+/// Runs a compile request once fewer than [`MAX_CONCURRENT_COMPILE_REQUESTS`] others are
+/// running. If the request has to wait, the user is told that it's queued, once per invocation.
+pub async fn with_compile_slot<T>(
+	ctx: Context<'_>,
+	request: impl std::future::Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+	let compile_slots = &ctx.data().compile_slots;
+	let _permit = match compile_slots.try_acquire() {
+		Ok(permit) => permit,
+		Err(_) => {
+			let mut show_queue_notice = false;
+			InvocationState::update(ctx, |state| {
+				show_queue_notice = !state.queue_notice_shown;
+				state.queue_notice_shown = true;
+			})
+			.await;
+			let queue_message = if show_queue_notice {
+				Some(ctx.say("Waiting in queue…").await?)
+			} else {
+				None
+			};
+			let permit = compile_slots.acquire().await?;
+			// Commands that reuse their response turn the queue message into the result, for the
+			// others it would linger around
+			if let Some(queue_message) = queue_message {
+				if !ctx.command().reuse_response {
+					queue_message.delete(ctx).await?;
+					InvocationState::update(ctx, |state| state.queue_notice_shown = false).await;
+				}
+			}
+			permit
+		}
+	};
+	request.await
+}
@@ -1,3 +1,4 @@
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -12,20 +13,36 @@ use crate::types::Data;
 
 pub mod checks;
 pub mod commands;
+pub mod database;
 pub mod helpers;
 pub mod types;
 
 #[shuttle_runtime::main]
-async fn serenity(#[shuttle_runtime::Secrets] secret_store: SecretStore) -> ShuttleSerenity {
+async fn serenity(
+	#[shuttle_runtime::Secrets] secret_store: SecretStore,
+	#[shuttle_shared_db::Postgres] database: sqlx::PgPool,
+) -> ShuttleSerenity {
 	let token = secret_store
 		.get("DISCORD_TOKEN")
 		.expect("Couldn't find your DISCORD_TOKEN!");
 	let intents = serenity::GatewayIntents::all();
+	let owners = checks::parse_owner_ids(secret_store.get("OWNER_IDS").as_deref())?;
+
+	debug!("Running database migrations...");
+	sqlx::migrate!()
+		.run(&database)
+		.await
+		.map_err(|e| anyhow!(e).context("Failed to run database migrations"))?;
 
 	let framework = poise::Framework::builder()
 		.setup(move |ctx, ready, framework| {
 			Box::pin(async move {
-				let data = Data::new(&secret_store)?;
+				let data = Data::new(&secret_store, database)?;
+				data.refresh_disabled_commands().await?;
+				data.refresh_mention_dm_modes().await?;
+				data.refresh_auto_delete_invocations().await?;
+				data.refresh_subscribed_threads().await?;
+				data.refresh_leaderboard_opt_outs().await?;
 
 				debug!("Registering commands...");
 				poise::builtins::register_in_guild(
@@ -40,7 +57,8 @@ async fn serenity(#[shuttle_runtime::Secrets] secret_store: SecretStore) -> Shut
 
 				load_or_create_modmail_message(ctx, &data).await?;
 
-				// let background_task_handle = tokio::spawn(async {}).await?;
+				commands::leaderboard::spawn_message_count_flusher(&data);
+				commands::server_icons::spawn_server_icon_changer(ctx, &data);
 
 				info!("rustbot logged in as {}", ready.user.name);
 				Ok(data)
@@ -49,33 +67,68 @@ async fn serenity(#[shuttle_runtime::Secrets] secret_store: SecretStore) -> Shut
 		.options(poise::FrameworkOptions {
 			commands: vec![
 				commands::crates::crate_(),
+				commands::crates::crate_deps(),
+				commands::crates::crates_trending(),
 				commands::crates::doc(),
 				commands::godbolt::godbolt(),
+				commands::godbolt::godbolt_compare(),
+				commands::godbolt::play_asm(),
 				commands::godbolt::mca(),
 				commands::godbolt::llvmir(),
+				commands::godbolt::asm_ir(),
 				commands::godbolt::targets(),
 				commands::utilities::go(),
 				commands::utilities::source(),
+				commands::utilities::feedback(),
 				commands::utilities::help(),
 				commands::utilities::register(),
+				commands::utilities::slash_sync(),
 				commands::utilities::uptime(),
+				commands::utilities::botinfo(),
+				commands::utilities::serverinfo(),
+				commands::utilities::avatar(),
+				commands::utilities::banner(),
+				commands::utilities::pin_list(),
+				commands::utilities::forward(),
+				commands::mentions::mention_dms(),
+				commands::auto_delete::auto_delete(),
+				commands::threads::remind_thread(),
+				commands::threads::unremind_thread(),
+				commands::leaderboard::leaderboard(),
 				commands::utilities::conradluget(),
 				commands::utilities::cleanup(),
 				commands::utilities::ban(),
 				commands::utilities::selftimeout(),
+				commands::utilities::selftimeout_cancel(),
+				commands::ban_sweep::ban_sweep(),
+				commands::rustup::rustup(),
+				commands::server_icons::roticon(),
+				commands::stats::stats(),
+				commands::twir::thisweekinrust(),
+				commands::utilities::disable(),
+				commands::utilities::enable(),
+				commands::utilities::disabled(),
 				commands::modmail::modmail(),
 				commands::modmail::modmail_context_menu_for_message(),
 				commands::modmail::modmail_context_menu_for_user(),
 				commands::modmail::modmail_setup(),
+				commands::modmail::modmail_whois(),
+				commands::onboarding::onboarding_setup(),
 				commands::playground::play(),
 				commands::playground::playwarn(),
 				commands::playground::eval(),
+				commands::playground::check(),
+				commands::playground::test(),
+				commands::playground::expect(),
 				commands::playground::miri(),
 				commands::playground::expand(),
 				commands::playground::clippy(),
 				commands::playground::fmt(),
 				commands::playground::microbench(),
+				commands::playground::minimize(),
 				commands::playground::procmacro(),
+				commands::playground::codesize(),
+				commands::playground::clean_codeblocks(),
 			],
 			prefix_options: poise::PrefixFrameworkOptions {
 				prefix: Some("?".into()),
@@ -127,7 +180,16 @@ code here
 							warn!("{}", e)
 						}
 					} else if let poise::FrameworkError::Command { ctx, error, .. } = error {
-						if let Err(e) = ctx.say(error.to_string()).await {
+						// Raw database errors aren't helpful to users, and might leak details
+						let response = match database::database_error(&error) {
+							Some(e) if database::is_connection_error(e) => {
+								ctx.data().database_health.record_failure();
+								database::UNAVAILABLE_MESSAGE.to_owned()
+							}
+							Some(_) => "Something went wrong with the database.".to_owned(),
+							None => error.to_string(),
+						};
+						if let Err(e) = ctx.say(response).await {
 							warn!("{}", e)
 						}
 					}
@@ -155,10 +217,13 @@ code here
 			post_command: |ctx| {
 				Box::pin(async move {
 					println!("Executed command {}!", ctx.command().qualified_name);
+					commands::auto_delete::delete_invocation_if_configured(ctx).await;
 				})
 			},
 			// Every command invocation must pass this check to continue execution
-			command_check: Some(|_ctx| Box::pin(async move { Ok(true) })),
+			command_check: Some(|ctx| Box::pin(checks::check_command_enabled(ctx))),
+			// Application owners are added on startup as well
+			owners,
 			// Enforce command checks even for owners (enforced by default)
 			// Set to true to bypass checks, which is useful for testing
 			skip_checks_for_owners: false,
@@ -189,24 +254,47 @@ async fn event_handler(
 		event.snake_case_name()
 	);
 
-	if let serenity::FullEvent::GuildMemberAddition { new_member } = event {
-		const RUSTIFICATION_DELAY: u64 = 30; // in minutes
-
-		tokio::time::sleep(std::time::Duration::from_secs(RUSTIFICATION_DELAY * 60)).await;
-
-		// Ignore errors because the user may have left already
-		let _: Result<_, _> = ctx
-			.http
-			.add_member_role(
-				new_member.guild_id,
-				new_member.user.id,
-				data.rustacean_role_id,
-				Some(&format!(
-					"Automatically rustified after {} minutes",
-					RUSTIFICATION_DELAY
-				)),
-			)
-			.await;
+	match event {
+		serenity::FullEvent::GuildMemberAddition { new_member } => match data.onboarding_mode {
+			commands::onboarding::OnboardingMode::Timed => {
+				const RUSTIFICATION_DELAY: u64 = 30; // in minutes
+
+				tokio::time::sleep(std::time::Duration::from_secs(RUSTIFICATION_DELAY * 60)).await;
+
+				// Ignore errors because the user may have left already
+				let _: Result<_, _> = commands::onboarding::give_rustacean_role(
+					ctx,
+					data,
+					new_member.user.id,
+					&format!(
+						"Automatically rustified after {} minutes",
+						RUSTIFICATION_DELAY
+					),
+				)
+				.await;
+			}
+			commands::onboarding::OnboardingMode::Gated => {
+				commands::onboarding::handle_member_addition(ctx, data, new_member).await?;
+			}
+		},
+		serenity::FullEvent::InteractionCreate { interaction } => {
+			commands::onboarding::handle_interaction(ctx, data, interaction).await?;
+		}
+		serenity::FullEvent::Ready { .. } => {
+			data.ready_count.fetch_add(1, Ordering::Relaxed);
+		}
+		serenity::FullEvent::Resume { .. } => {
+			data.resume_count.fetch_add(1, Ordering::Relaxed);
+		}
+		serenity::FullEvent::ReactionAdd { add_reaction } => {
+			commands::threads::handle_solved_reaction(ctx, add_reaction).await?;
+		}
+		serenity::FullEvent::Message { new_message } => {
+			commands::leaderboard::count_message(data, new_message);
+			commands::mentions::handle_message(ctx, data, new_message).await?;
+			commands::threads::handle_subscribed_thread_message(ctx, data, new_message).await?;
+		}
+		_ => {}
 	}
 
 	Ok(())
@@ -1,10 +1,12 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Error, Result};
 use poise::serenity_prelude as serenity;
 use shuttle_runtime::SecretStore;
 
-use crate::commands;
+use crate::{commands, database};
 
 #[derive(Debug)]
 pub struct Data {
@@ -14,13 +16,57 @@ pub struct Data {
 	pub rustacean_role_id: serenity::RoleId,
 	pub modmail_channel_id: serenity::ChannelId,
 	pub modmail_message: Arc<tokio::sync::RwLock<Option<serenity::Message>>>,
+	pub onboarding_mode: commands::onboarding::OnboardingMode,
+	/// Channel the code of conduct message is posted in. Only needed in the gated onboarding mode.
+	pub rules_channel_id: Option<serenity::ChannelId>,
 	pub bot_start_time: std::time::Instant,
+	/// Number of gateway Ready events, i.e. the initial connection plus full reconnects
+	pub ready_count: AtomicU32,
+	/// Number of gateway sessions that were resumed after a disconnect
+	pub resume_count: AtomicU32,
 	pub http: reqwest::Client,
-	pub godbolt_metadata: std::sync::Mutex<commands::godbolt::GodboltMetadata>,
+	/// Optional token used to create gists with GitHub's higher authenticated rate limit
+	pub github_token: Option<String>,
+	pub godbolt_metadata: tokio::sync::RwLock<commands::godbolt::GodboltMetadata>,
+	/// Held while the godbolt metadata is being fetched, so that only one fetch runs at a time
+	pub godbolt_metadata_refresh: tokio::sync::Mutex<()>,
+	/// Cached versions of the Rust release channels, see `?rustup`
+	pub toolchain_releases: std::sync::Mutex<commands::rustup::ToolchainReleases>,
+	/// Cached latest issue of This Week in Rust, see `?thisweekinrust`
+	pub twir_cache: std::sync::Mutex<commands::twir::TwirCache>,
+	/// Cached crates.io listings, see `?crates-trending`
+	pub trending_crates: std::sync::Mutex<commands::crates::TrendingCratesCache>,
+	pub database: sqlx::PgPool,
+	pub database_health: database::DatabaseHealth,
+	/// Names of commands that were disabled at runtime, mirrored from the database
+	pub disabled_commands: std::sync::RwLock<HashSet<String>>,
+	/// Channels and the commands whose invoking messages get deleted there, mirrored from the
+	/// database
+	pub auto_delete_invocations: std::sync::RwLock<HashSet<(serenity::ChannelId, String)>>,
+	/// Threads with active subscriptions, mirrored from the database
+	pub subscribed_threads: std::sync::RwLock<HashSet<serenity::ChannelId>>,
+	/// Message counts not yet written to the database, see `commands::leaderboard`
+	pub pending_message_counts: commands::leaderboard::PendingMessageCounts,
+	/// Users whose messages aren't counted for the leaderboard, mirrored from the database
+	pub leaderboard_opt_outs: std::sync::RwLock<HashSet<serenity::UserId>>,
+	/// Users that opted into DMs about mentions, mirrored from the database
+	pub mention_dm_modes:
+		std::sync::RwLock<HashMap<serenity::UserId, commands::mentions::MentionDmMode>>,
+	/// Limits how many compile requests to play.rust-lang.org and Godbolt run at the same time
+	pub compile_slots: tokio::sync::Semaphore,
+	/// When users with mention DMs last sent a message, to tell whether they're idle
+	pub last_activity: std::sync::Mutex<HashMap<serenity::UserId, std::time::Instant>>,
+	/// Where and when users last timed themselves out, and until when, so it can be undone within
+	/// a grace period
+	pub recent_self_timeouts: std::sync::Mutex<
+		HashMap<serenity::UserId, (serenity::GuildId, std::time::Instant, serenity::Timestamp)>,
+	>,
+	/// State of the server icon rotation, shared with its background task
+	pub server_icons: Arc<commands::server_icons::ServerIcons>,
 }
 
 impl Data {
-	pub fn new(secret_store: &SecretStore) -> Result<Self> {
+	pub fn new(secret_store: &SecretStore, database: sqlx::PgPool) -> Result<Self> {
 		Ok(Self {
 			discord_guild_id: secret_store
 				.get("DISCORD_GUILD")
@@ -56,14 +102,157 @@ impl Data {
 				.parse::<u64>()?
 				.into(),
 			modmail_message: Default::default(),
+			onboarding_mode: secret_store
+				.get("ONBOARDING_MODE")
+				.filter(|mode| !mode.is_empty())
+				.map_or(Ok(commands::onboarding::OnboardingMode::Timed), |mode| {
+					mode.parse()
+				})?,
+			rules_channel_id: secret_store
+				.get("RULES_CHANNEL_ID")
+				.filter(|id| !id.is_empty())
+				.map(|id| id.parse::<u64>())
+				.transpose()?
+				.map(Into::into),
 			bot_start_time: std::time::Instant::now(),
+			ready_count: AtomicU32::new(0),
+			resume_count: AtomicU32::new(0),
 			http: reqwest::Client::new(),
-			godbolt_metadata: std::sync::Mutex::new(commands::godbolt::GodboltMetadata::default()),
+			github_token: secret_store
+				.get("GITHUB_TOKEN")
+				.filter(|token| !token.is_empty()),
+			godbolt_metadata: Default::default(),
+			godbolt_metadata_refresh: Default::default(),
+			toolchain_releases: Default::default(),
+			twir_cache: Default::default(),
+			trending_crates: Default::default(),
+			database,
+			database_health: Default::default(),
+			disabled_commands: Default::default(),
+			auto_delete_invocations: Default::default(),
+			subscribed_threads: Default::default(),
+			pending_message_counts: Default::default(),
+			leaderboard_opt_outs: Default::default(),
+			mention_dm_modes: Default::default(),
+			compile_slots: tokio::sync::Semaphore::new(
+				crate::helpers::MAX_CONCURRENT_COMPILE_REQUESTS,
+			),
+			last_activity: Default::default(),
+			recent_self_timeouts: Default::default(),
+			server_icons: Arc::new(commands::server_icons::ServerIcons::new(
+				secret_store
+					.get("SERVER_ICON_INTERVAL_HOURS")
+					.filter(|interval| !interval.is_empty())
+					.map(|interval| interval.parse())
+					.transpose()?,
+			)),
 		})
 	}
+
+	/// Reloads the set of disabled commands from the database
+	pub async fn refresh_disabled_commands(&self) -> Result<()> {
+		let disabled_commands = database::disabled_commands::get_all(&self.database)
+			.await?
+			.into_iter()
+			.map(|command| command.command_name)
+			.collect();
+		*self.disabled_commands.write().unwrap() = disabled_commands;
+		Ok(())
+	}
+
+	/// Reloads the auto-deleted command invocations from the database
+	pub async fn refresh_auto_delete_invocations(&self) -> Result<()> {
+		let settings = database::auto_delete_invocations::get_all(&self.database)
+			.await?
+			.into_iter()
+			.map(|setting| {
+				(
+					serenity::ChannelId::new(setting.channel_id as u64),
+					setting.command_name,
+				)
+			})
+			.collect();
+		*self.auto_delete_invocations.write().unwrap() = settings;
+		Ok(())
+	}
+
+	/// Reloads the threads with active subscriptions from the database, dropping expired ones
+	pub async fn refresh_subscribed_threads(&self) -> Result<()> {
+		let thread_ids = database::thread_subscriptions::active_thread_ids(&self.database)
+			.await?
+			.into_iter()
+			.map(|thread_id| serenity::ChannelId::new(thread_id as u64))
+			.collect();
+		*self.subscribed_threads.write().unwrap() = thread_ids;
+		Ok(())
+	}
+
+	/// Reloads the users that opted out of the leaderboard from the database
+	pub async fn refresh_leaderboard_opt_outs(&self) -> Result<()> {
+		let user_ids = database::message_counts::get_opt_outs(&self.database)
+			.await?
+			.into_iter()
+			.map(|user_id| serenity::UserId::new(user_id as u64))
+			.collect();
+		*self.leaderboard_opt_outs.write().unwrap() = user_ids;
+		Ok(())
+	}
+
+	/// Reloads the mention DM settings from the database
+	pub async fn refresh_mention_dm_modes(&self) -> Result<()> {
+		let modes = database::mention_notifications::get_all(&self.database)
+			.await?
+			.into_iter()
+			.filter_map(|notification| {
+				let mode =
+					commands::mentions::MentionDmMode::from_database_name(&notification.mode)?;
+				Some((serenity::UserId::new(notification.user_id as u64), mode))
+			})
+			.collect();
+		*self.mention_dm_modes.write().unwrap() = modes;
+		Ok(())
+	}
 }
 
 pub type Context<'a> = poise::Context<'a, Data, Error>;
 
+/// State that's kept across reruns of the same invocation, e.g. when the message is edited.
+/// Poise only stores a single value as invocation data, so everything shares this struct: read it
+/// with [`InvocationState::get`] and change it with [`InvocationState::update`], which keeps the
+/// parts set by others.
+#[derive(Debug, Default, Clone)]
+pub struct InvocationState {
+	/// Whether the triggering message was already deleted, see `commands::auto_delete`
+	pub invocation_deleted: bool,
+	/// Flags chosen in the playground's flag editor, which apply when the invocation is run again.
+	/// Keys and values are like the ones of [`poise::KeyValueArgs`].
+	pub edited_flags: HashMap<String, String>,
+	/// Whether the author chose to run playground code that looks like it contains a secret
+	pub secret_confirmed: bool,
+	/// Whether the user was told that a compile request is queued. Commands that compile several
+	/// times at once should only tell them once.
+	pub queue_notice_shown: bool,
+}
+
+impl InvocationState {
+	pub async fn get(ctx: Context<'_>) -> Self {
+		ctx.invocation_data::<Self>()
+			.await
+			.map(|state| state.clone())
+			.unwrap_or_default()
+	}
+
+	pub async fn update(ctx: Context<'_>, update: impl FnOnce(&mut Self)) {
+		if let Some(mut state) = ctx.invocation_data::<Self>().await {
+			update(&mut state);
+			return;
+		}
+
+		let mut state = Self::default();
+		update(&mut state);
+		ctx.set_invocation_data(state).await;
+	}
+}
+
 // const EMBED_COLOR: (u8, u8, u8) = (0xf7, 0x4c, 0x00);
 pub const EMBED_COLOR: (u8, u8, u8) = (0xb7, 0x47, 0x00); // slightly less saturated